@@ -0,0 +1,36 @@
+# Tests of the Python bindings. Install the module with `maturin develop --release`, then run pytest
+import os
+
+import pytest
+
+import connect4_solver
+
+
+def test_solve():
+    assert connect4_solver.solve("7422341735647741166133573473242566") == 1
+    with pytest.raises(ValueError):
+        connect4_solver.solve("48")
+
+
+@pytest.mark.skipif(
+    not os.environ.get("CONNECT4_SLOW_TESTS"),
+    reason="solving the empty board takes too long for the regular test run, set CONNECT4_SLOW_TESTS to run it",
+)
+def test_solve_empty_board():
+    # The first player wins with their last stone
+    assert connect4_solver.solve("") == 1
+
+
+def test_best_move():
+    # Both C and G win, C is closer to the center
+    assert connect4_solver.best_move("445566") == 2
+    assert connect4_solver.best_move("675424577317223371452513267643443266156151") is None
+
+
+def test_board():
+    board = connect4_solver.Board("445566")
+    assert board.is_winning(2) and not board.is_winning(0)
+    assert board.play(2) == 7
+    assert str(board).splitlines()[-1] == "--XXXX-"
+    with pytest.raises(ValueError):
+        board.play(7)
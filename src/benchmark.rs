@@ -0,0 +1,127 @@
+//! Parsing of the benchmark files, where each line holds a sequence of moves and the score of the position
+//! they lead to, separated by a space, e.g. `2252576253462244111563365343671351441 -1`.
+
+use crate::board::{BitBoard, NotationError};
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead, BufReader};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// Error returned when parsing an invalid benchmark line, or reading a benchmark file fails.
+/// Lines are numbered from 1 in their file, and a line parsed on its own is line 1.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file can't be read
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The line doesn't hold a move sequence followed by a score
+    InvalidFormat { line: usize },
+    /// The score isn't an integer
+    InvalidScore { line: usize },
+    /// The move sequence isn't valid
+    InvalidMoves { line: usize, error: NotationError },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            ParseError::Io(error) => write!(f, "can't read the benchmark file: {error}"),
+            ParseError::InvalidFormat { line } => write!(f, "line {line} isn't a move sequence followed by a score"),
+            ParseError::InvalidScore { line } => write!(f, "invalid score on line {line}"),
+            ParseError::InvalidMoves { line, error } => write!(f, "invalid moves on line {line}: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            ParseError::Io(error) => Some(error),
+            ParseError::InvalidMoves { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+/// Parses a line of a benchmark file into the position and its expected score.
+/// Whitespace around and between the two fields is ignored.
+pub fn parse_benchmark_line(line: &str) -> Result<(BitBoard, i32), ParseError> {
+    parse_line(line, 1)
+}
+
+/// Reads the positions and expected scores of a benchmark file, in the order of its lines.
+/// Blank lines are skipped, and the first invalid line fails the whole file.
+#[cfg(feature = "std")]
+pub fn load_benchmark_file(path: impl AsRef<Path>) -> Result<Vec<(BitBoard, i32)>, ParseError> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let mut cases = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            cases.push(parse_line(&line, index + 1)?);
+        }
+    }
+    Ok(cases)
+}
+
+fn parse_line(text: &str, line: usize) -> Result<(BitBoard, i32), ParseError> {
+    let mut fields = text.split_whitespace();
+    let (Some(moves), Some(score), None) = (fields.next(), fields.next(), fields.next()) else {
+        return Err(ParseError::InvalidFormat { line });
+    };
+    let board: BitBoard = moves.parse().map_err(|error| ParseError::InvalidMoves { line, error })?;
+    let score = score.parse().map_err(|_| ParseError::InvalidScore { line })?;
+    Ok((board, score))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{Board, Column};
+
+    #[test]
+    fn test_parse_benchmark_line() {
+        let (board, score) = parse_benchmark_line("2252576253462244111563365343671351441 -1").unwrap();
+        assert_eq!(board.to_notation(), "2252576253462244111563365343671351441");
+        assert_eq!(score, -1);
+        assert_eq!(parse_benchmark_line("  4453\t 2 \r").unwrap(), (BitBoard::from_notation("4453"), 2));
+
+        assert!(matches!(parse_benchmark_line(""), Err(ParseError::InvalidFormat { line: 1 })));
+        assert!(matches!(parse_benchmark_line("4453"), Err(ParseError::InvalidFormat { line: 1 })));
+        assert!(matches!(parse_benchmark_line("4453 2 3"), Err(ParseError::InvalidFormat { line: 1 })));
+        assert!(matches!(parse_benchmark_line("4453 two"), Err(ParseError::InvalidScore { line: 1 })));
+        assert!(matches!(
+            parse_benchmark_line("1111111 0"),
+            Err(ParseError::InvalidMoves { line: 1, error: NotationError::ColumnFull { column: Column::A, index: 6 } })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_load_benchmark_file() {
+        let cases = load_benchmark_file("benchmarks/Test_L3_R1.txt").unwrap();
+        assert_eq!(cases.len(), 1000);
+        assert_eq!(cases[1], (BitBoard::from_notation("7422341735647741166133573473242566"), 1));
+
+        let path = std::env::temp_dir().join(format!("connect4_benchmark_{}.txt", std::process::id()));
+        std::fs::write(&path, "4453 2\n\n  \n44 0  \n").unwrap();
+        let cases = load_benchmark_file(&path).unwrap();
+        assert_eq!(cases, [(BitBoard::from_notation("4453"), 2), (BitBoard::from_notation("44"), 0)]);
+
+        std::fs::write(&path, "4453 2\n\n44 x\n").unwrap();
+        let error = load_benchmark_file(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(error, ParseError::InvalidScore { line: 3 }));
+        assert!(matches!(load_benchmark_file(&path), Err(ParseError::Io(_))));
+    }
+}
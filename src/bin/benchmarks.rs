@@ -1,19 +1,18 @@
 // Purpose: Benchmarks for the project.
-// Run with --release to get accurate results.
+// Run with --release to get accurate results. With --threads N the cases of a file are solved on N threads,
+// which is faster but makes the time of each case less accurate.
 use connect4_solver::prelude::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-fn read_lines(filename: &str) -> Vec<String> {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    reader.lines().flatten().collect()
-}
+const USAGE: &str = "Usage: benchmarks [--threads N]";
 
 struct CaseResult {
     correct: bool,
     time_taken_ns: u128,
     nodes_searched: usize,
+    branching_factor: f64,
+    table_stats: TableStats,
 }
 
 fn format_time_ns(ns: u128) -> String {
@@ -32,48 +31,59 @@ fn format_time_ns(ns: u128) -> String {
 // Run a benchmark with input from a file. Each line in a file contains the sequence of moves
 // and the expected score the engine should evaluate to
 // Outputs the average time taken to solve position, avg number of nodes searched, and avg node search rate.
-fn benchmark(file: &str, title: &str, per_case_output: bool) {
+// The cases are shared by `threads` workers, each with its own solver. Every case is solved with an empty table,
+// so the results are the same for any number of threads
+fn benchmark(file: &str, title: &str, per_case_output: bool, threads: usize) {
     println!("Running benchmark: {file} | {title}");
 
-    let mut solver = Solver::new();
     let now = std::time::Instant::now();
-    let lines = read_lines(file);
-    let results = lines
-        .iter()
-        .enumerate()
-        .map(|(index, line)| {
-            let mut splits = line.split(' ');
-            let moves = splits.next().unwrap();
-            let expected_score = splits.next().unwrap().parse::<i32>().unwrap();
-            let board = BitBoard::from_notation(moves);
-
-            solver.clear();
-            let now = std::time::Instant::now();
-            let result = solver.solve(&board);
-            let elapsed = now.elapsed().as_nanos();
-
-            let result = CaseResult {
-                correct: result.score == expected_score,
-                time_taken_ns: elapsed,
-                nodes_searched: result.nodes_searched,
-            };
-
-            if per_case_output {
-                println!(
-                    "Game #{}: {} - {}us - {} nodes - {} Kpos/s",
-                    index,
-                    if result.correct { "PASSED" } else { "FAILED" },
-                    elapsed,
-                    result.nodes_searched,
-                    result.nodes_searched as f32 / elapsed as f32 * 1_000.0
-                );
-            }
-
-            result
-        })
-        .collect::<Vec<_>>();
+    let cases = load_benchmark_file(file).unwrap_or_else(|error| panic!("{file}: {error}"));
+    let next_case = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(cases.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let mut solver = Solver::new();
+                loop {
+                    let index = next_case.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(board, expected_score)) = cases.get(index) else {
+                        break;
+                    };
+                    solver.clear();
+                    let result = solver.solve(&board);
+                    let result = CaseResult {
+                        correct: result.score == expected_score,
+                        time_taken_ns: result.elapsed.as_nanos(),
+                        nodes_searched: result.nodes_searched,
+                        branching_factor: result
+                            .effective_branching_factor((WIDTH * HEIGHT) as u32 - board.number_of_moves()),
+                        table_stats: solver.table_stats(),
+                    };
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
     let elapsed = now.elapsed().as_nanos();
 
+    // Back in the order of the file
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    let results: Vec<CaseResult> = results.into_iter().map(|(_, result)| result).collect();
+    if per_case_output {
+        for (index, result) in results.iter().enumerate() {
+            println!(
+                "Game #{}: {} - {}us - {} nodes - {} Kpos/s - EBF {:.3}",
+                index,
+                if result.correct { "PASSED" } else { "FAILED" },
+                result.time_taken_ns,
+                result.nodes_searched,
+                result.nodes_searched as f32 / result.time_taken_ns as f32 * 1_000.0,
+                result.branching_factor
+            );
+        }
+    }
+
     println!("Benchmark result: {}", file);
     println!("Time taken: {}", format_time_ns(elapsed));
     println!("Number of entries: {}", results.len());
@@ -98,18 +108,57 @@ fn benchmark(file: &str, title: &str, per_case_output: bool) {
             / results.iter().map(|r| r.time_taken_ns).sum::<u128>() as f32
             * 1_000_000.0
     );
+    // The geometric mean, since the branching factors multiply into node counts
+    println!(
+        "Average effective branching factor: {:.3}",
+        (results.iter().map(|r| r.branching_factor.ln()).sum::<f64>() / results.len() as f64).exp()
+    );
+    let table_stats = TableStats {
+        hits: results.iter().map(|r| r.table_stats.hits).sum(),
+        misses: results.iter().map(|r| r.table_stats.misses).sum(),
+        collisions: results.iter().map(|r| r.table_stats.collisions).sum(),
+    };
+    println!(
+        "Transposition table hit rate: {:.2}% ({} collisions)",
+        table_stats.hit_rate() * 100.0,
+        table_stats.collisions
+    );
+}
+
+// Returns the number of threads to solve the cases on, 1 by default
+fn parse_args() -> Result<usize, String> {
+    let mut threads = 1;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|threads| threads.parse().ok())
+                    .filter(|&threads| threads > 0)
+                    .ok_or("--threads expects a positive number of threads")?
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(threads)
 }
 
 fn main() {
-    benchmark("benchmarks/Test_L3_R1.txt", "End game - Easy", false);
+    let threads = parse_args().unwrap_or_else(|error| {
+        eprintln!("{error}\n{USAGE}");
+        std::process::exit(2);
+    });
+
+    benchmark("benchmarks/Test_L3_R1.txt", "End game - Easy", false, threads);
     println!("----------------");
-    benchmark("benchmarks/Test_L2_R1.txt", "Mid game - Easy", false);
+    benchmark("benchmarks/Test_L2_R1.txt", "Mid game - Easy", false, threads);
     println!("----------------");
-    benchmark("benchmarks/Test_L2_R2.txt", "Mid game - Medium", false);
+    benchmark("benchmarks/Test_L2_R2.txt", "Mid game - Medium", false, threads);
     println!("----------------");
-    benchmark("benchmarks/Test_L1_R1.txt", "Early game - Easy", false);
+    benchmark("benchmarks/Test_L1_R1.txt", "Early game - Easy", false, threads);
     println!("----------------");
-    benchmark("benchmarks/Test_L1_R2.txt", "Early game - Medium", false);
+    benchmark("benchmarks/Test_L1_R2.txt", "Early game - Medium", false, threads);
     println!("----------------");
-    benchmark("benchmarks/Test_L1_R3.txt", "Early game - Hard", true);
+    benchmark("benchmarks/Test_L1_R3.txt", "Early game - Hard", true, threads);
 }
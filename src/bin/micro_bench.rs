@@ -0,0 +1,86 @@
+// Purpose: Microbenchmarks of the board primitives, isolated from the search.
+// Run with --release to get accurate results.
+use connect4_solver::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::{Rng, SeedableRng};
+use std::hint::black_box;
+use strum::IntoEnumIterator;
+
+const N_POSITIONS: usize = 100_000;
+const REPETITIONS: usize = 10;
+
+// Generates positions by playing random non-winning moves from the empty board.
+// Positions where the current player can win immediately are skipped, since
+// possible_nonlosing_moves must not be called on them.
+fn random_positions(rng: &mut impl Rng, count: usize) -> Vec<BitBoard> {
+    let mut positions = Vec::with_capacity(count);
+    while positions.len() < count {
+        let mut board = BitBoard::new();
+        let plies = rng.gen_range(0..(WIDTH * HEIGHT) as u32 - 2);
+        while board.number_of_moves() < plies {
+            let column = Column::iter()
+                .filter(|&c| board.is_playable(c) && !board.is_winning(c))
+                .choose(rng);
+            match column {
+                Some(column) => board.play(column),
+                None => break,
+            };
+        }
+
+        if !board.can_win_in_one_move() {
+            positions.push(board);
+        }
+    }
+    positions
+}
+
+// Times `op` over every position and reports the rate of operations per second.
+// `op` returns the number of operations it performed on the position.
+fn bench(name: &str, positions: &[BitBoard], mut op: impl FnMut(&BitBoard) -> usize) {
+    let mut ops = 0;
+    let now = std::time::Instant::now();
+    for _ in 0..REPETITIONS {
+        for position in positions {
+            ops += op(black_box(position));
+        }
+    }
+    let elapsed = now.elapsed().as_secs_f64();
+    println!("{:<26} {:>10.2} Mops/s", name, ops as f64 / elapsed / 1_000_000.0);
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let positions = random_positions(&mut rng, N_POSITIONS);
+    println!("Running micro benchmarks: {} positions x {} repetitions", positions.len(), REPETITIONS);
+
+    bench("is_winning", &positions, |board| {
+        for column in Column::iter() {
+            black_box(board.is_winning(column));
+        }
+        WIDTH
+    });
+    bench("possible_nonlosing_moves", &positions, |board| {
+        black_box(board.possible_nonlosing_moves());
+        1
+    });
+    bench("moves", &positions, |board| {
+        black_box(board.moves());
+        1
+    });
+    bench("score_move", &positions, |board| {
+        for column in Column::iter() {
+            black_box(board.score_move(column));
+        }
+        WIDTH
+    });
+    bench("play", &positions, |board| {
+        let mut ops = 0;
+        for column in Column::iter().filter(|&c| board.is_playable(c)) {
+            let mut next = *board;
+            black_box(next.play(column));
+            ops += 1;
+        }
+        ops
+    });
+}
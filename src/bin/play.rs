@@ -0,0 +1,94 @@
+// Purpose: Play a game against the solver in the terminal.
+// Run with --release. The solver searches its first moves to the end of the game, which can take minutes.
+use connect4_solver::prelude::*;
+use std::io::{self, BufRead, Write};
+
+const USAGE: &str = "Usage: play [--first human|solver] [--table-bits BITS]";
+
+struct Options {
+    human_first: bool,
+    table_bits: u32,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        human_first: true,
+        table_bits: 23,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--first" => {
+                options.human_first = match args.next().as_deref() {
+                    Some("human") => true,
+                    Some("solver") => false,
+                    _ => return Err("--first expects human or solver".to_string()),
+                }
+            }
+            "--table-bits" => {
+                let min_bits = TranspositionTable::MIN_BITS;
+                options.table_bits = args
+                    .next()
+                    .and_then(|bits| bits.parse().ok())
+                    .filter(|&bits| bits >= min_bits)
+                    .ok_or(format!("--table-bits expects a number of bits, at least {min_bits}"))?
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+// Prompts until a playable column is entered. Returns None at the end of the input
+fn read_move(board: &BitBoard, input: &mut impl BufRead) -> Option<Column> {
+    loop {
+        print!("Your move (1-{WIDTH}): ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if input.read_line(&mut line).unwrap() == 0 {
+            return None;
+        }
+
+        let mut chars = line.trim().chars();
+        match (chars.next().map(Column::try_from), chars.next()) {
+            (Some(Ok(column)), None) if board.is_playable(column) => return Some(column),
+            (Some(Ok(column)), None) => println!("Column {} is full", char::from(column)),
+            _ => println!("Enter a column from 1 to {WIDTH}"),
+        }
+    }
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|error| {
+        eprintln!("{error}\n{USAGE}");
+        std::process::exit(2);
+    });
+
+    let mut solver = Solver::with_table_bits(options.table_bits);
+    let mut board = BitBoard::new();
+    let human = if options.human_first { Player::One } else { Player::Two };
+    let mut input = io::stdin().lock();
+    println!("You play {}", if human == Player::One { 'X' } else { 'O' });
+
+    loop {
+        print!("\n{}", board.pretty());
+        match board.status() {
+            GameStatus::InProgress => {}
+            GameStatus::Won(player) if player == human => return println!("You win!"),
+            GameStatus::Won(_) => return println!("The solver wins"),
+            GameStatus::Drawn => return println!("Draw"),
+        }
+
+        let column = if board.current_player() == human {
+            match read_move(&board, &mut input) {
+                Some(column) => column,
+                None => return,
+            }
+        } else {
+            let (column, score) = solver.best_move(&board).unwrap();
+            println!("The solver plays {} (score {score})", char::from(column));
+            column
+        };
+        board.play(column);
+    }
+}
@@ -1,13 +1,88 @@
 //! This module contains the board trait and all board implementations
 
-use static_assertions as sa;
-use std::fmt::Display;
+use alloc::{string::String, vec::Vec};
+use core::fmt::Display;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use strum::IntoEnumIterator;
 use strum_macros::{EnumCount, EnumIter, FromRepr};
 
+mod analysis_board;
+mod array_board;
+mod bitboard;
+mod pop_out_board;
+pub use analysis_board::AnalysisBoard;
+pub use array_board::ArrayBoard;
+pub use bitboard::{BitBoard, FingerprintError, InvalidGrid, SizedBitBoard};
+pub use pop_out_board::{PopOutBoard, PopOutMove};
+
 pub const WIDTH: usize = 7;
 pub const HEIGHT: usize = 6;
 
-#[derive(Copy, Clone, PartialEq, Debug, EnumIter, FromRepr, EnumCount)]
+/// Every line of four cells that wins the game, as (row, column) coordinates with row 0 at the bottom
+pub const ALL_LINES: &[[(usize, usize); 4]] = &generate_lines();
+
+// Generate the lines from the board geometry: horizontal, vertical and both diagonals
+const N_LINES: usize = (WIDTH - 3) * HEIGHT + WIDTH * (HEIGHT - 3) + 2 * (WIDTH - 3) * (HEIGHT - 3);
+const fn generate_lines() -> [[(usize, usize); 4]; N_LINES] {
+    // (row step, column step, first row, first column) of each direction, the diagonal going down-right
+    // starts on the 4th row so that all its cells stay on the board
+    const DIRECTIONS: [(isize, isize, usize, usize); 4] = [(0, 1, 0, 0), (1, 0, 0, 0), (1, 1, 0, 0), (-1, 1, 3, 0)];
+    let mut lines = [[(0, 0); 4]; N_LINES];
+    let mut index = 0;
+    let mut direction = 0;
+    while direction < DIRECTIONS.len() {
+        let (row_step, column_step, first_row, first_column) = DIRECTIONS[direction];
+        let mut row = first_row;
+        while row < HEIGHT {
+            let mut column = first_column;
+            while column < WIDTH {
+                let last_row = row as isize + 3 * row_step;
+                let last_column = column as isize + 3 * column_step;
+                if last_row >= 0 && last_row < HEIGHT as isize && last_column < WIDTH as isize {
+                    let mut cell = 0;
+                    while cell < 4 {
+                        lines[index][cell] = (
+                            (row as isize + cell as isize * row_step) as usize,
+                            (column as isize + cell as isize * column_step) as usize,
+                        );
+                        cell += 1;
+                    }
+                    index += 1;
+                }
+                column += 1;
+            }
+            row += 1;
+        }
+        direction += 1;
+    }
+
+    assert!(index == N_LINES);
+    lines
+}
+
+// Random value of a stone of each player (indexed by Player) on each cell, at the cell's bit in a [BitBoard],
+// generated with splitmix64 from a fixed seed so that Zobrist keys are the same on every run
+const ZOBRIST_VALUES: [[u64; 2]; WIDTH * (HEIGHT + 1)] = generate_zobrist_values();
+// Random value of player Two being the one to move, the next value of the same generator
+const ZOBRIST_TWO_TO_MOVE: u64 = 0xd809_c198_fac5_eafc;
+const fn generate_zobrist_values() -> [[u64; 2]; WIDTH * (HEIGHT + 1)] {
+    let mut values = [[0; 2]; WIDTH * (HEIGHT + 1)];
+    let mut state: u64 = 0x4334_5a6f_6272_6973;
+    let mut i = 0;
+    while i < 2 * values.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        values[i / 2][i % 2] = z ^ (z >> 31);
+        i += 1;
+    }
+    values
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter, FromRepr, EnumCount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Column {
     A = 0,
     B,
@@ -18,24 +93,144 @@ pub enum Column {
     G,
 }
 
-impl From<char> for Column {
-    fn from(c: char) -> Self {
+impl Column {
+    /// Returns every column, from left to right. Unlike `Column::iter()`, it can be used in const contexts
+    pub const fn all() -> [Column; WIDTH] {
+        [Column::A, Column::B, Column::C, Column::D, Column::E, Column::F, Column::G]
+    }
+
+    /// Returns every column from the center out, the right one first of two columns as close to the center:
+    /// D, E, C, F, B, G, A. This is the order in which the solver searches the moves with the same priority
+    pub const fn center_out() -> [Column; WIDTH] {
+        let all = Column::all();
+        let mid = (WIDTH - 1) / 2;
+        let mut order = [Column::A; WIDTH];
+        let mut index = 0;
+        while index < WIDTH {
+            let offset = index.div_ceil(2);
+            order[index] = all[if index % 2 == 1 { mid + offset } else { mid - offset }];
+            index += 1;
+        }
+        order
+    }
+}
+
+static_assertions::const_assert!(Column::center_out()[0] as usize == Column::D as usize);
+
+/// Error returned when converting a character that isn't a column, holding the rejected character
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InvalidColumnChar(pub char);
+
+impl Display for InvalidColumnChar {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid column '{}'", self.0)
+    }
+}
+
+impl core::error::Error for InvalidColumnChar {}
+
+/// Parses a column from its number (1 to 7) or letter (A to G, in either case)
+impl TryFrom<char> for Column {
+    type Error = InvalidColumnChar;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            '1' | 'A' | 'a' => Column::A,
-            '2' | 'B' | 'b' => Column::B,
-            '3' | 'C' | 'c' => Column::C,
-            '4' | 'D' | 'd' => Column::D,
-            '5' | 'E' | 'e' => Column::E,
-            '6' | 'F' | 'f' => Column::F,
-            '7' | 'G' | 'g' => Column::G,
-            _ => panic!("Invalid column"),
+            '1' | 'A' | 'a' => Ok(Column::A),
+            '2' | 'B' | 'b' => Ok(Column::B),
+            '3' | 'C' | 'c' => Ok(Column::C),
+            '4' | 'D' | 'd' => Ok(Column::D),
+            '5' | 'E' | 'e' => Ok(Column::E),
+            '6' | 'F' | 'f' => Ok(Column::F),
+            '7' | 'G' | 'g' => Ok(Column::G),
+            _ => Err(InvalidColumnChar(c)),
         }
     }
 }
 
+/// Returns the number of the column, from '1' to '7', as used in move sequences
+impl From<Column> for char {
+    fn from(column: Column) -> Self {
+        (b'1' + column as u8) as char
+    }
+}
+
+/// One of the two players. Player One makes the first move of the game, except in some positions built by
+/// [SizedBitBoard::from_grid()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// The content of a cell of the board, see [Board::cell()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Empty,
+    /// A piece of [Player::One]
+    P1,
+    /// A piece of [Player::Two]
+    P2,
+}
+
+/// How [Board::render()] draws the cells of a board
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum RenderStyle {
+    /// X for player One, O for player Two and - for empty cells, as the Display of the boards
+    #[default]
+    Ascii,
+    /// Red and yellow disc emoji, and white circles for empty cells. They're two columns wide in a monospace
+    /// terminal, and the column numbers are padded to match
+    Unicode,
+    /// Red and yellow blocks colored with ANSI escape codes, and - for empty cells
+    Ansi,
+}
+
+impl RenderStyle {
+    // Returns the text of a cell, and its width in a monospace terminal
+    fn cell(self, cell: Cell) -> (&'static str, usize) {
+        match (self, cell) {
+            (RenderStyle::Ascii, Cell::Empty) => ("-", 1),
+            (RenderStyle::Ascii, Cell::P1) => ("X", 1),
+            (RenderStyle::Ascii, Cell::P2) => ("O", 1),
+            (RenderStyle::Unicode, Cell::Empty) => ("\u{26aa}", 2),
+            (RenderStyle::Unicode, Cell::P1) => ("\u{1f534}", 2),
+            (RenderStyle::Unicode, Cell::P2) => ("\u{1f7e1}", 2),
+            (RenderStyle::Ansi, Cell::Empty) => ("-", 1),
+            (RenderStyle::Ansi, Cell::P1) => ("\x1b[31m\u{2588}\x1b[0m", 1),
+            (RenderStyle::Ansi, Cell::P2) => ("\x1b[33m\u{2588}\x1b[0m", 1),
+        }
+    }
+}
+
+/// The moves of the current player, classified by [Board::moves()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Moves {
+    /// Playing in the column wins the game
+    ImmediateWin(Column),
+    /// No move wins, and these are the moves that don't let the opponent win on their next move,
+    /// in the format of [Board::possible_nonlosing_moves()]
+    NonLosing(BitBoardField),
+    /// No move wins, and every move lets the opponent win on their next move
+    Lost,
+}
+
+/// The state of a game, see [Board::status()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameStatus {
+    InProgress,
+    Won(Player),
+    /// The board is full without a four-in-a-row
+    Drawn,
+}
+
 /// A scored move, containing the column and the score of the move.
 /// This struct is returned by the [Board::score_move()] method
+///
+/// Scored moves are ordered by score, and moves with the same score by their column from the center out,
+/// as in [Column::center_out()], so that the center column is the greatest. This is a total order, so a
+/// max-heap of moves pops them in the same order whatever order they were pushed in.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScoredMove {
     pub column: Column,
     pub score: u32,
@@ -49,24 +244,55 @@ impl PartialEq for ScoredMove {
 }
 
 impl Ord for ScoredMove {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.score.cmp(&other.score)
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        let center_rank = |column| Column::center_out().iter().position(|&c| c == column);
+        self.score.cmp(&other.score).then_with(|| center_rank(other.column).cmp(&center_rank(self.column)))
     }
 }
 
 impl PartialOrd for ScoredMove {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
+/// The threats a move creates and blocks, returned by the [Board::score_move_detailed()] method
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetailedMove {
+    pub column: Column,
+    /// Number of cells where the current player would complete a four-in-a-row after the move,
+    /// the same as the score of [Board::score_move()]
+    pub threats_created: u32,
+    /// Number of cells where the opponent would complete a four-in-a-row that the move fills, at most one
+    pub threats_blocked: u32,
+}
+
 pub type BitBoardField = u64;
 
 /// A Connect 4 board that can be played on or passed into a solver
-pub trait Board: Copy {
+pub trait Board: Clone {
+    /// Number of columns of the board, at most the standard width. Columns from this number on aren't playable
+    const WIDTH: usize = WIDTH;
+    /// Number of rows of the board
+    const HEIGHT: usize = HEIGHT;
+    /// Number of pieces in a row that win the game
+    const WIN_LENGTH: usize = 4;
+
     /// Checks if a given column is playable, i.e. if there is still space in the column
     fn is_playable(&self, column: Column) -> bool;
 
+    /// Returns which columns can be played in, i.e. aren't full, indexed by column.
+    /// Unlike [Board::possible_nonlosing_moves()] this ignores tactics, and it doesn't check whether
+    /// the game is over either. Columns from [Board::WIDTH] on are never playable.
+    fn legal_moves(&self) -> [bool; WIDTH] {
+        let mut moves = [false; WIDTH];
+        for column in Column::iter() {
+            moves[column as usize] = self.is_playable(column);
+        }
+        moves
+    }
+
     /// Checks if playing a piece in the given column would result in a win by the current player
     fn is_winning(&self, column: Column) -> bool;
 
@@ -76,378 +302,326 @@ pub trait Board: Copy {
     /// This method should not be called if the move is not playable or winning
     fn play(&mut self, column: Column) -> u32;
 
+    /// Reverses the last move, which must have been played in the given column.
+    /// Returns the number of played moves.
+    fn unplay(&mut self, column: Column) -> u32;
+
+    /// Clears the board in place, back to the empty board. The [Board::history()] is cleared too,
+    /// so moves played before can no longer be reversed.
+    fn reset(&mut self)
+    where
+        Self: Default,
+    {
+        *self = Self::default();
+    }
+
     /// Returns the number of moves made so far
     fn number_of_moves(&self) -> u32;
 
-    /// Returns the unique key that represented the position.
-    fn key(&self) -> u64;
-
-    /// Returns a board representation containing the possible non-losing moves.
-    /// A value of 0 means there are no possible non-losing moves.
-    /// A move is non-losing if it doesn't result in an immediate win for the opponent
-    /// Thing function should not be called if there is a move that immediately wins the game
-    /// for the current player. To check that, use [Board::can_win_in_one_move()]
-    fn possible_nonlosing_moves(&self) -> BitBoardField;
+    /// Returns the player to move. By default player One moves first, and the players alternate from there
+    fn current_player(&self) -> Player {
+        if self.number_of_moves().is_multiple_of(2) {
+            Player::One
+        } else {
+            Player::Two
+        }
+    }
 
-    /// Returns whether the current player can win in the next move
-    fn can_win_in_one_move(&self) -> bool;
+    /// Returns the moves played since the board was created, in order. For a board created from the empty board
+    /// or a sequence of moves, these are all the moves of the game. Reversed moves are removed.
+    fn history(&self) -> &[Column];
 
-    /// Returns the score of a move. The higher the score, the better the move
-    fn score_move(&self, column: Column) -> ScoredMove;
-}
+    /// Returns the sequence of moves of [Board::history()], one column number per move.
+    /// The stones alone don't tell in which order they were played, so this relies on the history:
+    /// only for a board created from the empty board can it be passed back to `from_notation`.
+    fn to_notation(&self) -> String {
+        self.history().iter().map(|&column| char::from(column)).collect()
+    }
 
-// Implementation of a Bitboard
+    /// Returns whether the board can produce a stable key for the position. Boards that can't are searched
+    /// without the transposition table, and [Board::key()] and [Board::canonical_key()] must not be called on them.
+    fn has_key(&self) -> bool {
+        true
+    }
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct BitBoard {
-    n_moves: usize,
-    pos: BitBoardField, // stores the positions of the pieces of the current player
-    mask: BitBoardField, // marks all non-empty cells
-}
+    /// Returns the unique key that represented the position.
+    fn key(&self) -> u64;
 
-sa::const_assert!(std::mem::size_of::<BitBoardField>() <= (HEIGHT + 1) * WIDTH);
+    /// Returns the key of the position or of its mirror image, whichever is smaller.
+    /// A position and its mirror image have the same canonical key.
+    fn canonical_key(&self) -> u64;
 
-impl Board for BitBoard {
-    #[inline]
-    fn is_playable(&self, column: Column) -> bool {
-        self.mask & BitBoard::top_mask_col(column) == 0
-    }
+    /// Returns a Zobrist hash of the position: the XOR of a fixed random value for each stone, depending on
+    /// its cell and player, and of another one when player Two is to move.
+    /// Unlike [Board::key()] it isn't unique, but all its 64 bits are evenly distributed.
+    fn zobrist_key(&self) -> u64;
 
-    #[inline]
-    fn is_winning(&self, column: Column) -> bool {
-        self.possible_moves() & self.winning_position() & BitBoard::column_mask(column) != 0
-    }
+    /// Returns a board representation containing the possible non-losing moves, the cell on row `r` of column `c`
+    /// being bit `c * (Self::HEIGHT + 1) + r`. A value of 0 means there are no possible non-losing moves.
+    /// A move is non-losing if it doesn't result in an immediate win for the opponent
+    /// Thing function should not be called if there is a move that immediately wins the game
+    /// for the current player, which is only checked by a debug assertion. To check that, use
+    /// [Board::can_win_in_one_move()], or use [Board::moves()] to get both at once.
+    fn possible_nonlosing_moves(&self) -> BitBoardField;
 
-    #[inline]
-    fn number_of_moves(&self) -> u32 {
-        self.n_moves as u32
+    /// Returns a winning move of the current player if there is one, otherwise their non-losing moves.
+    /// Unlike [Board::possible_nonlosing_moves()] it can be called on any position that isn't over.
+    fn moves(&self) -> Moves {
+        if let Some(column) = Column::iter().find(|&column| self.is_winning(column)) {
+            return Moves::ImmediateWin(column);
+        }
+        match self.possible_nonlosing_moves() {
+            0 => Moves::Lost,
+            moves => Moves::NonLosing(moves),
+        }
     }
 
-    #[inline]
-    fn play(&mut self, column: Column) -> u32 {
-        self.pos ^= self.mask; // switch player
-        self.mask |= self.mask + BitBoard::bottom_mask_col(column); // play in the column
+    /// Returns whether the game is over, and who won it. Only the player who made the last move can have
+    /// a four-in-a-row, since the game would have ended before the opponent's.
+    fn status(&self) -> GameStatus;
 
-        self.n_moves += 1;
-        self.n_moves as u32
+    /// Returns whether every cell of the board has a piece
+    fn is_full(&self) -> bool {
+        self.number_of_moves() == (Self::WIDTH * Self::HEIGHT) as u32
     }
 
-    #[inline]
-    fn key(&self) -> u64 {
-        self.pos + self.mask
+    /// Returns whether the game is over without a winner, see [GameStatus::Drawn]
+    fn is_draw(&self) -> bool {
+        self.status() == GameStatus::Drawn
     }
 
-    #[inline]
-    fn can_win_in_one_move(&self) -> bool {
-        self.possible_moves() & self.winning_position() != 0
-    }
+    /// Returns whether the current player can win in the next move
+    fn can_win_in_one_move(&self) -> bool;
 
-    fn possible_nonlosing_moves(&self) -> BitBoardField {
-        assert!(!self.can_win_in_one_move(),
-        "Called possible_nonlosing_moves but there is a move that immediately wins the game for the current player");
+    /// Returns the number of empty cells where a piece of `player` would complete a four-in-a-row,
+    /// whether the cell can be played now or only once the cells below it are filled
+    fn threats(&self, player: Player) -> u32;
 
-        let mut possible = self.possible_moves();
-        let opponent_win = self.opponent_winning_position();
-        let forced_moves = possible & opponent_win;
-        if forced_moves != 0 {
-            if forced_moves & (forced_moves - 1) != 0 {
-                // more than one forced move, we can't do anything
-                return 0;
-            }
+    /// Returns the score of a move. The higher the score, the better the move
+    fn score_move(&self, column: Column) -> ScoredMove;
 
-            possible = forced_moves;
-        }
+    /// Returns the threats created and blocked by a move in the given column, which must be playable
+    fn score_move_detailed(&self, column: Column) -> DetailedMove;
 
-        // Don't play directly under an opponent's winning position as well
-        possible & !(opponent_win >> 1)
-    }
+    /// Checks if playing in the given column would leave the opponent with two or more squares
+    /// they can immediately win on, which can't all be blocked.
+    /// Returns false if the move wins the game for the current player.
+    fn creates_opponent_fork(&self, column: Column) -> bool;
 
-    // The score is the number of winning positions after the move
-    fn score_move(&self, column: Column) -> ScoredMove {
-        let move_bitmask = (self.mask + BitBoard::bottom_mask_col(column)) & BitBoard::column_mask(column);
-        let score = BitBoard::compute_winning_position(self.pos | move_bitmask, self.mask).count_ones();
-        ScoredMove {
-            column,
-            score,
-        }
-    }
-}
+    /// Checks if the current player has two squares to win on that the opponent couldn't both block if it were
+    /// their turn: two that can be played now, or one that can be played now with the other directly above it,
+    /// which blocking the first one would give away. A move leaving the player with one is an unstoppable win.
+    fn has_double_threat(&self) -> bool;
 
-impl BitBoard {
-    // 1 on the bottom row of each column
-    const BOTTOM_MASK: BitBoardField = BitBoard::bottom(WIDTH, HEIGHT);
-    // 1 on every cell of the board
-    const BOARD_MASK: BitBoardField = BitBoard::BOTTOM_MASK * ((1 << HEIGHT) - 1);
+    /// Returns the row (0 being the bottom) where the next piece played in each column would land,
+    /// or None for columns that are full
+    fn landing_rows(&self) -> [Option<usize>; WIDTH];
 
-    pub fn new() -> Self {
-        let mut height = [0; WIDTH];
-        for (i, item) in height.iter_mut().enumerate() {
-            *item = i * (HEIGHT + 1);
-        }
+    /// Returns the content of the cell on the given row (0 being the bottom) of the given column,
+    /// which must be on the board
+    fn cell(&self, row: usize, column: usize) -> Cell;
 
-        BitBoard {
-            n_moves: 0,
-            pos: 0,
-            mask: 0,
-        }
+    /// Returns the board as text for a terminal, richer than its Display: the column numbers above the cells,
+    /// spaced out, then whose turn it is or how the game ended. Player One is X and player Two is O
+    fn pretty(&self) -> String {
+        self.render(RenderStyle::Ascii)
     }
 
-    pub fn from_notation(notation: &str) -> Self {
-        let mut board = BitBoard::new();
-        for c in notation.chars() {
-            let column = Column::from(c);
-            board.play(column);
+    /// Returns the board as [Board::pretty()] does, drawing the pieces in the given style
+    fn render(&self, style: RenderStyle) -> String {
+        let (_, width) = style.cell(Cell::Empty);
+        let symbol = |player: Player| style.cell(if player == Player::One { Cell::P1 } else { Cell::P2 }).0;
+        let mut s = String::new();
+        // The row above the top one holds the column numbers, padded to the width of the cells
+        for row in (0..=Self::HEIGHT).rev() {
+            for column in 0..Self::WIDTH {
+                if column > 0 {
+                    s.push(' ');
+                }
+                if row == Self::HEIGHT {
+                    s.push(char::from(Column::from_repr(column).unwrap()));
+                    s.extend(core::iter::repeat_n(' ', width - 1));
+                } else {
+                    s += style.cell(self.cell(row, column)).0;
+                }
+            }
+            s.truncate(s.trim_end_matches(' ').len());
+            s.push('\n');
         }
-        board
-    }
 
-    #[inline]
-    fn bottom_mask_col(column: Column) -> BitBoardField {
-        1 << (column as usize * (HEIGHT + 1))
-    }
-
-    #[inline]
-    pub fn column_mask(column: Column) -> BitBoardField {
-        ((1 << HEIGHT) - 1) << (column as usize * (HEIGHT + 1))
-    }
-
-    #[inline]
-    fn top_mask_col(column: Column) -> BitBoardField {
-        1 << (HEIGHT - 1) << (column as usize * (HEIGHT + 1))
+        let n_moves = self.number_of_moves();
+        s += &match self.status() {
+            GameStatus::InProgress => alloc::format!("Move {}, {} to play\n", n_moves + 1, symbol(self.current_player())),
+            GameStatus::Won(player) => alloc::format!("{} won in {n_moves} moves\n", symbol(player)),
+            GameStatus::Drawn => alloc::format!("Draw in {n_moves} moves\n"),
+        };
+        s
     }
 
-    #[inline]
-    fn possible_moves(&self) -> BitBoardField {
-        (self.mask + BitBoard::BOTTOM_MASK) & BitBoard::BOARD_MASK
+    /// Returns a uniformly random playable column, or None if the board is full
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Column> {
+        Column::iter().filter(|&c| self.is_playable(c)).choose(rng)
     }
 
-    // Returns a bitmask of the possible winning moves for the current player
-    fn winning_position(&self) -> BitBoardField {
-        BitBoard::compute_winning_position(self.pos, self.mask)
+    /// Plays uniformly random moves for both players until the game ends.
+    /// Returns 1 if the current player wins, -1 if the opponent wins and 0 for a draw.
+    fn random_playout(&self, rng: &mut impl Rng) -> i32 {
+        let mut board = self.clone();
+        let mut sign = 1;
+        while let Some(column) = board.random_move(rng) {
+            if board.is_winning(column) {
+                return sign;
+            }
+            board.play(column);
+            sign = -sign;
+        }
+        0
     }
+}
 
-    // Returns a bitmask of the possible winning moves for the opponent
-    fn opponent_winning_position(&self) -> BitBoardField {
-        BitBoard::compute_winning_position(self.pos ^ self.mask, self.mask)
-    }
+/// Error returned when parsing a position from an invalid move sequence
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NotationError {
+    /// The character at byte `index` isn't a column
+    InvalidCharacter { character: char, index: usize },
+    /// The move at byte `index` is played in a column that is already full
+    ColumnFull { column: Column, index: usize },
+}
 
-    // Recursively construct a bitmask with 1 on the bottom row of each column
-    const fn bottom(width: usize, height: usize) -> BitBoardField {
-        if width == 0 {
-            0
-        } else {
-            1 << ((height + 1) * (width - 1)) | BitBoard::bottom(width - 1, height)
+impl Display for NotationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NotationError::InvalidCharacter { character, index } => {
+                write!(f, "invalid column '{character}' at index {index}")
+            }
+            NotationError::ColumnFull { column, index } => write!(f, "column {column:?} is full at index {index}"),
         }
     }
+}
 
-    // Returns a bitmask of the possible winning moves for the current position (player) and mask
-    const fn compute_winning_position(
-        position: BitBoardField,
-        mask: BitBoardField,
-    ) -> BitBoardField {
-        let mut moves = 0;
-
-        // Resulting bitmask is the actual move, because of the shifts
-        let vertical = (position << 1) & (position << 2) & (position << 3);
-        moves |= vertical;
-
-        let horizontal = (position << (HEIGHT + 1)) & (position << (2 * (HEIGHT + 1)));
-        moves |= horizontal & (position << (3 * (HEIGHT + 1))); // horizontally to the left
-        moves |= horizontal & (position >> (HEIGHT + 1)); // horizontally to the right
-
-        let horizontal = (position >> (HEIGHT + 1)) & (position >> (2 * (HEIGHT + 1)));
-        moves |= horizontal & (position >> (3 * (HEIGHT + 1))); // horizontally to the right
-        moves |= horizontal & (position << (HEIGHT + 1)); // horizontally to the left
-
-        // Diagonal 1
-        let diag = (position << HEIGHT) & (position << (2 * HEIGHT));
-        moves |= diag & (position << (3 * HEIGHT)); // diagonally to the left
-        moves |= diag & (position >> HEIGHT); // diagonally to the right
-
-        let diag = (position >> HEIGHT) & (position >> (2 * HEIGHT));
-        moves |= diag & (position >> (3 * HEIGHT)); // diagonally to the right
-        moves |= diag & (position << HEIGHT); // diagonally to the left
+impl core::error::Error for NotationError {}
 
-        // Diagonal 2
-        let diag = (position << (HEIGHT + 2)) & (position << (2 * (HEIGHT + 2)));
-        moves |= diag & (position << (3 * (HEIGHT + 2))); // diagonally to the left
-        moves |= diag & (position >> (HEIGHT + 2)); // diagonally to the right
+/// Error returned when parsing a position from a move sequence that isn't a game in progress,
+/// see [SizedBitBoard::from_notation_checked()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameError {
+    /// The sequence isn't a valid notation
+    Notation(NotationError),
+    /// The move at byte `index` wins the game or fills the board
+    GameOver { index: usize },
+}
 
-        let diag = (position >> (HEIGHT + 2)) & (position >> (2 * (HEIGHT + 2)));
-        moves |= diag & (position >> (3 * (HEIGHT + 2))); // diagonally to the right
-        moves |= diag & (position << (HEIGHT + 2)); // diagonally to the left
+impl From<NotationError> for GameError {
+    fn from(error: NotationError) -> Self {
+        GameError::Notation(error)
+    }
+}
 
-        moves & (mask ^ BitBoard::BOARD_MASK)
+impl Display for GameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GameError::Notation(error) => error.fmt(f),
+            GameError::GameOver { index } => write!(f, "the game is over after the move at index {index}"),
+        }
     }
 }
 
-impl Display for BitBoard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = String::new();
-        for row in (0..HEIGHT).rev() {
-            for column in 0..WIDTH {
-                let pos = 1 << (row + column * (HEIGHT + 1));
-                let is_stone = self.mask & pos != 0;
-                let is_stone_current_player = self.pos & pos != 0;
-                let is_p1 = self.n_moves % 2 == 0;
-
-                if is_stone {
-                    if is_stone_current_player {
-                        s.push(if is_p1 { 'X' } else { 'O' });
-                    } else {
-                        s.push(if is_p1 { 'O' } else { 'X' });
-                    }
-                } else {
-                    s.push('-');
-                }
-            }
-            s.push('\n');
+impl core::error::Error for GameError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            GameError::Notation(error) => Some(error),
+            GameError::GameOver { .. } => None,
         }
-        write!(f, "{}", s)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    use std::collections::BinaryHeap;
-    use strum::IntoEnumIterator;
-
-    #[test]
-    fn test_is_playable() {
-        let mut board = BitBoard::new();
-        Column::iter().for_each(|c| assert!(board.is_playable(c)));
-
-        for _ in 0..HEIGHT {
-            board.play(Column::A);
-        }
-
-        assert!(!board.is_playable(Column::A));
-    }
-
-    #[test]
-    fn test_is_winning_horizontal() {
-        let board = BitBoard::from_notation("435462");
-        assert!(board.is_winning(Column::G));
-        assert!(board.can_win_in_one_move());
-        Column::iter()
-            .filter(|&c| c != Column::G)
-            .for_each(|c| assert!(!board.is_winning(c)));
-    }
+    use alloc::{string::ToString, vec};
 
     #[test]
-    fn test_is_winning_vertical() {
-        let board = BitBoard::from_notation("123451121517");
-        assert!(board.is_winning(Column::A));
-        assert!(board.can_win_in_one_move());
-        Column::iter()
-            .skip(1)
-            .for_each(|c| assert!(!board.is_winning(c)));
-    }
+    fn test_is_draw() {
+        let mut board = BitBoard::from_notation("67542457731722337145251326764344326615615");
+        assert!(!board.is_full() && !board.is_draw());
+        board.play(Column::A);
+        assert!(board.is_full() && board.is_draw());
 
-    #[test]
-    fn test_is_winning_diagonal() {
-        let board = BitBoard::from_notation("453433222");
-        assert!(board.is_winning(Column::B));
-        assert!(board.can_win_in_one_move());
-
-        let board = BitBoard::from_notation("2334454551");
-        assert!(board.is_winning(Column::E));
-        assert!(board.can_win_in_one_move());
+        // The last piece fills the board with a four-in-a-row
+        let mut board = ArrayBoard::from_notation("55722526747744416255216414711237533661363");
+        board.play(Column::C);
+        assert!(board.is_full() && !board.is_draw());
+        assert!(SizedBitBoard::<4, 4>::from_notation("1234123421432143").is_full());
     }
 
     #[test]
-    fn test_is_winning_2_places() {
-        let board = BitBoard::from_notation("445362322111");
-        assert!(board.is_winning(Column::A)); // diagonal win
-        assert!(board.is_winning(Column::G)); // horizontal win
-        assert!(board.can_win_in_one_move());
-
-        Column::iter()
-            .filter(|&c| c != Column::G && c != Column::A)
-            .for_each(|c| assert!(!board.is_winning(c)));
+    fn test_pretty() {
+        let empty_row = "- - - - - - -\n";
+        assert_eq!(
+            BitBoard::from_notation("4453").pretty(),
+            "1 2 3 4 5 6 7\n".to_string()
+                + &empty_row.repeat(HEIGHT - 2)
+                + "- - - O - - -\n- - O X X - -\nMove 5, X to play\n"
+        );
+        assert!(BitBoard::from_notation("4455667").pretty().ends_with("\n- - - X X X X\nX won in 7 moves\n"));
+        assert_eq!(SizedBitBoard::<2, 2>::from_notation("1122").pretty(), "1 2\nO O\nX X\nDraw in 4 moves\n");
     }
 
     #[test]
-    fn test_play() {
-        let mut board = BitBoard::new();
-        assert_eq!(board.play(Column::D), 1);
-        assert_eq!(board.play(Column::E), 2);
-        assert_eq!(board.play(Column::D), 3);
-        assert_eq!(board.play(Column::G), 4);
+    fn test_render() {
+        let board = SizedBitBoard::<3, 2>::from_notation("12");
+        assert_eq!(board.render(RenderStyle::Ascii), board.pretty());
+        assert_eq!(board.render(RenderStyle::Unicode), "1  2  3\n⚪ ⚪ ⚪\n🔴 🟡 ⚪\nMove 3, 🔴 to play\n");
+        assert_eq!(
+            board.render(RenderStyle::Ansi),
+            "1 2 3\n- - -\n\x1b[31m█\x1b[0m \x1b[33m█\x1b[0m -\nMove 3, \x1b[31m█\x1b[0m to play\n"
+        );
     }
 
     #[test]
-    fn test_possible_nonlosing_moves() {
-        let board = BitBoard::new();
-        let moves = board.possible_nonlosing_moves();
-        assert!(Column::iter().all(|c| moves & BitBoard::column_mask(c) != 0)); // all columns are possible
-
-        // Losing position
-        let board = BitBoard::from_notation("4453623221115");
-
-        // Player 2's turn, but player 1 can win in A or G. So there's nothing player 2 can do
-        assert_eq!(board.possible_nonlosing_moves(), 0); // all columns are losing
-
-        // Player 1 can win in E
-        let mut board = BitBoard::from_notation("2334465545");
-        assert!(board.is_winning(Column::E));
-        board.play(Column::A); // don't win yet
-        assert!(board.possible_nonlosing_moves() & BitBoard::column_mask(Column::E) != 0); // only E is possible otherwise p1 wins
-        assert!(Column::iter()
-            .filter(|&c| c != Column::E)
-            .all(|c| board.possible_nonlosing_moves() & BitBoard::column_mask(c) == 0)); // other columns are losing
+    fn test_column_try_from() {
+        assert_eq!(Column::try_from('4'), Ok(Column::D));
+        assert_eq!(Column::try_from('g'), Ok(Column::G));
+        assert_eq!(Column::try_from('8'), Err(InvalidColumnChar('8')));
+
+        let columns = "abc".chars().map(Column::try_from).collect::<Result<Vec<_>, _>>();
+        assert_eq!(columns, Ok(vec![Column::A, Column::B, Column::C]));
+        let columns = "a_c".chars().map(Column::try_from).collect::<Result<Vec<_>, _>>();
+        assert_eq!(columns, Err(InvalidColumnChar('_')));
+
+        for column in Column::iter() {
+            assert_eq!(Column::try_from(char::from(column)), Ok(column));
+        }
     }
 
     #[test]
-    fn test_move_scoring() {
-        // A move with higher score is a move that creates possible wins by forming a connected 3 line
-        let board = BitBoard::from_notation("4655");
-
-        // Playing C will create a possible win in the next move, so it's score should be 1
-        assert_eq!(board.score_move(Column::C).score, 1);
-
-        let board = BitBoard::from_notation("465556677141");
-        // Playing C allows 2 wins in the next move (by playing eiter B or F)
-        // Additionally, playing F also allows 2 wins (in C or G, although G is not yet possible)
-        // or B allows 1 win in the next move by playing C
-        assert_eq!(board.score_move(Column::C).score, 2);
-        assert_eq!(board.score_move(Column::F).score, 2);
-        assert_eq!(board.score_move(Column::B).score, 1);
+    fn test_column_orders() {
+        assert_eq!(Column::all().to_vec(), Column::iter().collect::<Vec<_>>());
+        assert_eq!(
+            Column::center_out(),
+            [Column::D, Column::E, Column::C, Column::F, Column::B, Column::G, Column::A]
+        );
+        const CENTER: Column = Column::center_out()[0];
+        assert_eq!(CENTER, Column::D);
     }
 
     #[test]
-    fn move_sorting_with_heap() {
-        let mut heap = BinaryHeap::new();
-
-        // We want the insertion order preserved in case of moves with the same score (stable sort)
-        let move1 = ScoredMove {
-            column: Column::A,
-            score: 1,
-        };
-        let move2 = ScoredMove {
-            column: Column::B,
-            score: 2,
-        };
-        let move3 = ScoredMove {
-            column: Column::C,
-            score: 2,
-        };
-        let move4 = ScoredMove {
-            column: Column::D,
-            score: 1,
-        };
-
-        // expected sequence is B, C, A, D
-        heap.push(move1);
-        heap.push(move2);
-        heap.push(move3);
-        heap.push(move4);
-
-        assert_eq!(heap.pop(), Some(move2));
-        assert_eq!(heap.pop(), Some(move3));
-        assert_eq!(heap.pop(), Some(move1));
-        assert_eq!(heap.pop(), Some(move4));
+    fn test_all_lines() {
+        assert_eq!(ALL_LINES.len(), 69);
+
+        let mut unique = ALL_LINES.to_vec();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ALL_LINES.len());
+
+        // Every line is made of adjacent cells on the board
+        for line in ALL_LINES {
+            assert!(line.iter().all(|&(row, column)| row < HEIGHT && column < WIDTH));
+            assert!(line.windows(2).all(|cells| {
+                cells[0].0.abs_diff(cells[1].0) <= 1 && cells[0].1.abs_diff(cells[1].1) <= 1
+            }));
+        }
+        assert!(ALL_LINES.contains(&[(0, 3), (1, 4), (2, 5), (3, 6)]));
+        assert!(ALL_LINES.contains(&[(5, 0), (4, 1), (3, 2), (2, 3)]));
     }
 }
@@ -0,0 +1,178 @@
+//! Board wrapper recording the positions played on it, e.g. to inspect what a search went through.
+
+use super::*;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+/// A board that forwards every call to the wrapped board, and records the `(key, number of moves)` of the
+/// position reached by each [Board::play()] in a log. Clones share their log, so the positions played on the
+/// clones a solver makes are recorded too. Boards without a key record their [Board::zobrist_key()] instead.
+///
+/// The log isn't thread safe, so the board can't be solved in parallel.
+#[derive(Clone, Debug)]
+pub struct AnalysisBoard<B: Board> {
+    board: B,
+    log: Rc<RefCell<Vec<(u64, u32)>>>,
+}
+
+impl<B: Board> AnalysisBoard<B> {
+    pub fn new(board: B) -> Self {
+        Self {
+            board,
+            log: Rc::default(),
+        }
+    }
+
+    /// Returns the wrapped board
+    pub fn inner(&self) -> &B {
+        &self.board
+    }
+
+    /// Returns the positions recorded so far, in the order they were played, and empties the log
+    pub fn take_log(&self) -> Vec<(u64, u32)> {
+        self.log.take()
+    }
+}
+
+impl<B: Board> Board for AnalysisBoard<B> {
+    const WIDTH: usize = B::WIDTH;
+    const HEIGHT: usize = B::HEIGHT;
+    const WIN_LENGTH: usize = B::WIN_LENGTH;
+
+    fn is_playable(&self, column: Column) -> bool {
+        self.board.is_playable(column)
+    }
+
+    fn is_winning(&self, column: Column) -> bool {
+        self.board.is_winning(column)
+    }
+
+    fn play(&mut self, column: Column) -> u32 {
+        let n_moves = self.board.play(column);
+        let key = if self.board.has_key() { self.board.key() } else { self.board.zobrist_key() };
+        self.log.borrow_mut().push((key, n_moves));
+        n_moves
+    }
+
+    fn unplay(&mut self, column: Column) -> u32 {
+        self.board.unplay(column)
+    }
+
+    fn number_of_moves(&self) -> u32 {
+        self.board.number_of_moves()
+    }
+
+    fn current_player(&self) -> Player {
+        self.board.current_player()
+    }
+
+    fn history(&self) -> &[Column] {
+        self.board.history()
+    }
+
+    fn has_key(&self) -> bool {
+        self.board.has_key()
+    }
+
+    fn key(&self) -> u64 {
+        self.board.key()
+    }
+
+    fn canonical_key(&self) -> u64 {
+        self.board.canonical_key()
+    }
+
+    fn zobrist_key(&self) -> u64 {
+        self.board.zobrist_key()
+    }
+
+    fn possible_nonlosing_moves(&self) -> BitBoardField {
+        self.board.possible_nonlosing_moves()
+    }
+
+    fn moves(&self) -> Moves {
+        self.board.moves()
+    }
+
+    fn status(&self) -> GameStatus {
+        self.board.status()
+    }
+
+    fn can_win_in_one_move(&self) -> bool {
+        self.board.can_win_in_one_move()
+    }
+
+    fn threats(&self, player: Player) -> u32 {
+        self.board.threats(player)
+    }
+
+    fn score_move(&self, column: Column) -> ScoredMove {
+        self.board.score_move(column)
+    }
+
+    fn score_move_detailed(&self, column: Column) -> DetailedMove {
+        self.board.score_move_detailed(column)
+    }
+
+    fn creates_opponent_fork(&self, column: Column) -> bool {
+        self.board.creates_opponent_fork(column)
+    }
+
+    fn has_double_threat(&self) -> bool {
+        self.board.has_double_threat()
+    }
+
+    fn landing_rows(&self) -> [Option<usize>; WIDTH] {
+        self.board.landing_rows()
+    }
+
+    fn cell(&self, row: usize, column: usize) -> Cell {
+        self.board.cell(row, column)
+    }
+}
+
+impl<B: Board + Display> Display for AnalysisBoard<B> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.board.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_log() {
+        let mut board = AnalysisBoard::new(BitBoard::from_notation("4453"));
+        board.play(Column::A);
+        let clone = board.clone();
+        board.play(Column::B);
+        assert_eq!(
+            clone.take_log(),
+            [
+                (BitBoard::from_notation("44531").key(), 5),
+                (BitBoard::from_notation("445312").key(), 6)
+            ]
+        );
+        assert!(board.take_log().is_empty());
+
+        board.unplay(Column::B);
+        assert_eq!(board.inner().to_notation(), "44531");
+        assert!(board.take_log().is_empty());
+    }
+
+    #[test]
+    fn test_solve() {
+        let position = BitBoard::from_notation("17516442226766");
+        let board = AnalysisBoard::new(position);
+        let result = Solver::new().solve(&board);
+        assert_eq!(result.score, Solver::new().solve(&position).score);
+
+        // Every searched node but the root is reached by a move, and the root is searched once per iteration
+        let log = board.take_log();
+        let iterations = Solver::new().solve_iter(&position).count();
+        assert_eq!(log.len(), result.nodes_searched - iterations);
+        assert!(log.iter().all(|&(_, n_moves)| n_moves > position.number_of_moves()));
+    }
+}
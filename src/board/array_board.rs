@@ -0,0 +1,433 @@
+//! Implementation of a board stored as a grid of cells. It is much slower than [BitBoard],
+//! but simple enough to serve as a reference to check the bitboard against.
+
+use super::*;
+
+#[derive(Copy, Clone, Debug)]
+pub struct ArrayBoard {
+    cells: [[Option<Player>; HEIGHT]; WIDTH], // cells[column][row], row 0 being the bottom
+    height: [usize; WIDTH],                   // number of pieces in each column
+    n_moves: usize,
+    history: [Column; WIDTH * HEIGHT], // moves played since the board was created, the first n_moves are valid
+    winning: u8, // columns where the current player would complete a four-in-a-row, updated after every move
+}
+
+impl Default for ArrayBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Board for ArrayBoard {
+    fn is_playable(&self, column: Column) -> bool {
+        self.height[column as usize] < HEIGHT
+    }
+
+    fn is_winning(&self, column: Column) -> bool {
+        self.winning & 1 << column as usize != 0
+    }
+
+    fn number_of_moves(&self) -> u32 {
+        self.n_moves as u32
+    }
+
+    fn play(&mut self, column: Column) -> u32 {
+        let column = column as usize;
+        self.history[self.n_moves] = Column::from_repr(column).unwrap();
+        self.cells[column][self.height[column]] = Some(self.current_player());
+        self.height[column] += 1;
+
+        self.n_moves += 1;
+        self.winning = self.winning_columns();
+        self.n_moves as u32
+    }
+
+    fn unplay(&mut self, column: Column) -> u32 {
+        let column = column as usize;
+        debug_assert!(self.height[column] != 0, "Called unplay on an empty column");
+        self.height[column] -= 1;
+        self.cells[column][self.height[column]] = None;
+
+        self.n_moves -= 1;
+        self.winning = self.winning_columns();
+        self.n_moves as u32
+    }
+
+    fn history(&self) -> &[Column] {
+        &self.history[..self.n_moves]
+    }
+
+    // Same key as the equivalent BitBoard: in each column, the stones of the current player plus the column mask
+    fn key(&self) -> u64 {
+        (0..WIDTH).map(|column| self.column_key(column) << (column * (HEIGHT + 1))).sum()
+    }
+
+    fn canonical_key(&self) -> u64 {
+        let mirror = (0..WIDTH)
+            .map(|column| self.column_key(WIDTH - 1 - column) << (column * (HEIGHT + 1)))
+            .sum();
+        self.key().min(mirror)
+    }
+
+    fn zobrist_key(&self) -> u64 {
+        let mut key = if self.current_player() == Player::Two { ZOBRIST_TWO_TO_MOVE } else { 0 };
+        for column in 0..WIDTH {
+            for row in 0..self.height[column] {
+                let player = self.cells[column][row].unwrap() as usize;
+                key ^= ZOBRIST_VALUES[column * (HEIGHT + 1) + row][player];
+            }
+        }
+        key
+    }
+
+    fn status(&self) -> GameStatus {
+        let last_player = self.opponent();
+        let won = ALL_LINES
+            .iter()
+            .any(|line| line.iter().all(|&(row, column)| self.cells[column][row] == Some(last_player)));
+        if won {
+            GameStatus::Won(last_player)
+        } else if self.is_full() {
+            GameStatus::Drawn
+        } else {
+            GameStatus::InProgress
+        }
+    }
+
+    fn can_win_in_one_move(&self) -> bool {
+        self.winning != 0
+    }
+
+    fn possible_nonlosing_moves(&self) -> BitBoardField {
+        debug_assert!(!self.can_win_in_one_move(),
+        "Called possible_nonlosing_moves but there is a move that immediately wins the game for the current player");
+
+        let opponent = self.opponent();
+        let playable: Vec<usize> = (0..WIDTH).filter(|&column| self.height[column] < HEIGHT).collect();
+        let forced: Vec<usize> = playable
+            .iter()
+            .copied()
+            .filter(|&column| self.completes_line(opponent, column, self.height[column]))
+            .collect();
+        if forced.len() > 1 {
+            // more than one forced move, we can't do anything
+            return 0;
+        }
+
+        let candidates = if forced.is_empty() { playable } else { forced };
+        candidates
+            .into_iter()
+            // Don't play directly under an opponent's winning position as well
+            .filter(|&column| {
+                let above = self.height[column] + 1;
+                above >= HEIGHT || !self.completes_line(opponent, column, above)
+            })
+            .fold(0, |moves, column| moves | 1 << (column * (HEIGHT + 1) + self.height[column]))
+    }
+
+    // The score is the number of winning positions after the move, as for BitBoard
+    fn score_move(&self, column: Column) -> ScoredMove {
+        let mut next = *self;
+        let player = self.current_player();
+        next.cells[column as usize][self.height[column as usize]] = Some(player);
+
+        let mut score = 0;
+        for c in 0..WIDTH {
+            for row in self.height[c]..HEIGHT {
+                if next.completes_line(player, c, row) {
+                    score += 1;
+                }
+            }
+        }
+
+        ScoredMove { column, score }
+    }
+
+    fn score_move_detailed(&self, column: Column) -> DetailedMove {
+        DetailedMove {
+            column,
+            threats_created: self.score_move(column).score,
+            threats_blocked: self.completes_line(self.opponent(), column as usize, self.height[column as usize]) as u32,
+        }
+    }
+
+    // Marks the empty cell of each line where the player has the three other cells, a cell can be in several
+    fn threats(&self, player: Player) -> u32 {
+        let mut threats = [[false; HEIGHT]; WIDTH];
+        for line in ALL_LINES {
+            let owned = line.iter().filter(|&&(row, column)| self.cells[column][row] == Some(player)).count();
+            let empty = line.iter().find(|&&(row, column)| self.cells[column][row].is_none());
+            if let (3, Some(&(row, column))) = (owned, empty) {
+                threats[column][row] = true;
+            }
+        }
+        threats.iter().flatten().filter(|&&threat| threat).count() as u32
+    }
+
+    fn creates_opponent_fork(&self, column: Column) -> bool {
+        if self.is_winning(column) {
+            return false;
+        }
+
+        let mut next = *self;
+        next.play(column);
+        Column::iter().filter(|&c| next.is_winning(c)).count() >= 2
+    }
+
+    // Checks the playable cells and the cells right above them
+    fn has_double_threat(&self) -> bool {
+        let player = self.current_player();
+        let mut playable_wins = 0;
+        for column in (0..WIDTH).filter(|&column| self.height[column] < HEIGHT) {
+            let row = self.height[column];
+            if self.completes_line(player, column, row) {
+                playable_wins += 1;
+                if row + 1 < HEIGHT && self.completes_line(player, column, row + 1) {
+                    return true;
+                }
+            }
+        }
+        playable_wins >= 2
+    }
+
+    fn landing_rows(&self) -> [Option<usize>; WIDTH] {
+        self.height.map(|height| (height < HEIGHT).then_some(height))
+    }
+
+    fn cell(&self, row: usize, column: usize) -> Cell {
+        debug_assert!(row < HEIGHT && column < WIDTH, "Cell ({row}, {column}) isn't on the board");
+        match self.cells[column][row] {
+            Some(Player::One) => Cell::P1,
+            Some(Player::Two) => Cell::P2,
+            None => Cell::Empty,
+        }
+    }
+}
+
+impl ArrayBoard {
+    // (column step, row step) of each direction: horizontal, vertical and both diagonals
+    const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+    pub fn new() -> Self {
+        ArrayBoard {
+            cells: [[None; HEIGHT]; WIDTH],
+            height: [0; WIDTH],
+            n_moves: 0,
+            history: [Column::A; WIDTH * HEIGHT],
+            winning: 0,
+        }
+    }
+
+    pub fn from_notation(notation: &str) -> Self {
+        let mut board = ArrayBoard::new();
+        for c in notation.chars() {
+            board.play(Column::try_from(c).unwrap());
+        }
+        board
+    }
+
+    fn opponent(&self) -> Player {
+        match self.current_player() {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+
+    // Returns the playable columns where the current player would complete a four-in-a-row, by bit of the column
+    fn winning_columns(&self) -> u8 {
+        let player = self.current_player();
+        (0..WIDTH)
+            .filter(|&column| self.height[column] < HEIGHT && self.completes_line(player, column, self.height[column]))
+            .fold(0, |columns, column| columns | 1 << column)
+    }
+
+    // Checks if a stone of the player on the given cell would be part of a four-in-a-row,
+    // counting the player's stones on both sides of the cell in each direction
+    fn completes_line(&self, player: Player, column: usize, row: usize) -> bool {
+        ArrayBoard::DIRECTIONS.iter().any(|&(dc, dr)| {
+            let count = |sign: isize| {
+                (1..4)
+                    .map(|i| (column as isize + sign * i * dc, row as isize + sign * i * dr))
+                    .take_while(|&(c, r)| {
+                        (0..WIDTH as isize).contains(&c)
+                            && (0..HEIGHT as isize).contains(&r)
+                            && self.cells[c as usize][r as usize] == Some(player)
+                    })
+                    .count()
+            };
+            count(1) + count(-1) >= 3
+        })
+    }
+
+    // Returns the column's stones of the current player plus its mask, aligned on the bottom bit
+    fn column_key(&self, column: usize) -> u64 {
+        let player = self.current_player();
+        let stones: u64 = (0..self.height[column])
+            .filter(|&row| self.cells[column][row] == Some(player))
+            .map(|row| 1 << row)
+            .sum();
+        stones + (1 << self.height[column]) - 1
+    }
+}
+
+impl Display for ArrayBoard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = String::new();
+        for row in (0..HEIGHT).rev() {
+            for column in 0..WIDTH {
+                s.push(match self.cells[column][row] {
+                    Some(Player::One) => 'X',
+                    Some(Player::Two) => 'O',
+                    None => '-',
+                });
+            }
+            s.push('\n');
+        }
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[cfg(feature = "std")]
+    use rand::rngs::StdRng;
+    #[cfg(feature = "std")]
+    use rand::SeedableRng;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_matches_bitboard() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut array = ArrayBoard::new();
+            let mut bitboard = BitBoard::new();
+            while let Some(column) = bitboard.random_move(&mut rng) {
+                assert_eq!(array.key(), bitboard.key());
+                assert_eq!(array.canonical_key(), bitboard.canonical_key());
+                assert_eq!(array.zobrist_key(), bitboard.zobrist_key());
+                assert_eq!(array.status(), GameStatus::InProgress);
+                assert_eq!(array.landing_rows(), bitboard.landing_rows());
+                assert_eq!(array.to_string(), bitboard.to_string());
+                assert_eq!(array.pretty(), bitboard.pretty());
+                assert_eq!(array.history(), bitboard.history());
+                assert_eq!(array.threats(Player::One), bitboard.threats(Player::One));
+                assert_eq!(array.threats(Player::Two), bitboard.threats(Player::Two));
+                assert_eq!(array.has_double_threat(), bitboard.has_double_threat());
+                for (row, column) in (0..HEIGHT).flat_map(|row| (0..WIDTH).map(move |column| (row, column))) {
+                    assert_eq!(array.cell(row, column), bitboard.cell(row, column));
+                }
+                for c in Column::iter() {
+                    assert_eq!(array.is_playable(c), bitboard.is_playable(c));
+                    assert_eq!(array.is_winning(c), bitboard.is_winning(c));
+                    if array.is_playable(c) {
+                        assert_eq!(array.score_move(c), bitboard.score_move(c));
+                        assert_eq!(array.score_move_detailed(c), bitboard.score_move_detailed(c));
+                        assert_eq!(array.creates_opponent_fork(c), bitboard.creates_opponent_fork(c));
+                    }
+                }
+
+                assert_eq!(array.moves(), bitboard.moves());
+                if bitboard.can_win_in_one_move() {
+                    assert!(array.can_win_in_one_move());
+                    let column = Column::iter().find(|&c| bitboard.is_winning(c)).unwrap();
+                    let player = bitboard.current_player();
+                    array.play(column);
+                    bitboard.play(column);
+                    assert_eq!(array.status(), GameStatus::Won(player));
+                    assert_eq!(bitboard.status(), GameStatus::Won(player));
+                    break;
+                }
+                assert_eq!(array.possible_nonlosing_moves(), bitboard.possible_nonlosing_moves());
+
+                array.play(column);
+                bitboard.play(column);
+                assert_eq!(array.number_of_moves(), bitboard.number_of_moves());
+            }
+        }
+    }
+
+    // Plays the notation on both boards, and checks that they agree on the moves of the position
+    #[cfg(feature = "std")]
+    fn assert_board_agreement<B1: Board + Default, B2: Board + Default>(notation: &str) {
+        let (mut board1, mut board2) = (B1::default(), B2::default());
+        for c in notation.chars() {
+            let column = Column::try_from(c).unwrap();
+            board1.play(column);
+            board2.play(column);
+        }
+
+        assert_eq!(board1.can_win_in_one_move(), board2.can_win_in_one_move(), "{notation}");
+        if !board1.can_win_in_one_move() {
+            assert_eq!(board1.possible_nonlosing_moves(), board2.possible_nonlosing_moves(), "{notation}");
+        }
+        for column in Column::iter() {
+            assert_eq!(board1.is_playable(column), board2.is_playable(column), "{notation} {column:?}");
+            assert_eq!(board1.is_winning(column), board2.is_winning(column), "{notation} {column:?}");
+            if board1.is_playable(column) {
+                assert_eq!(board1.score_move(column), board2.score_move(column), "{notation} {column:?}");
+                assert_eq!(
+                    board1.score_move_detailed(column),
+                    board2.score_move_detailed(column),
+                    "{notation} {column:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_board_agreement() {
+        for file in ["Test_L3_R1", "Test_L2_R1", "Test_L2_R2", "Test_L1_R1", "Test_L1_R2", "Test_L1_R3"] {
+            for (board, _) in crate::benchmark::load_benchmark_file(format!("benchmarks/{file}.txt")).unwrap() {
+                assert_board_agreement::<BitBoard, ArrayBoard>(&board.to_notation());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unplay() {
+        let mut board = ArrayBoard::from_notation("4453");
+        let key = board.key();
+        board.play(Column::D);
+        assert_eq!(board.unplay(Column::D), 4);
+        assert_eq!(board.key(), key);
+
+        board.reset();
+        assert_eq!(board.key(), ArrayBoard::new().key());
+        assert!(board.history().is_empty());
+    }
+
+    #[test]
+    fn test_winning_columns() {
+        // The winning columns are updated by every move, for the player to move
+        let mut board = ArrayBoard::from_notation("44556");
+        assert!(!board.can_win_in_one_move());
+        board.play(Column::F);
+        assert!(board.is_winning(Column::C) && board.is_winning(Column::G) && !board.is_winning(Column::A));
+        board.unplay(Column::F);
+        board.play(Column::G);
+        assert!(board.is_winning(Column::C) && !board.is_winning(Column::G));
+        board.reset();
+        assert!(!board.can_win_in_one_move());
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut solver = Solver::new();
+        for notation in [
+            "2252576253462244111563365343671351441",
+            "5554224333234511764415115",
+            "1233722555341451114725221333",
+        ] {
+            let array = solver.solve(&ArrayBoard::from_notation(notation));
+            solver.clear();
+            let bitboard = solver.solve(&BitBoard::from_notation(notation));
+            solver.clear();
+            assert_eq!(array.score, bitboard.score, "{notation}");
+            assert_eq!(array.nodes_searched, bitboard.nodes_searched, "{notation}");
+        }
+    }
+}
@@ -0,0 +1,1801 @@
+//! Implementation of a bitboard, storing the position in two 64 bit integers
+
+use super::*;
+use static_assertions as sa;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// Error returned when decoding a position from an invalid fingerprint
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FingerprintError {
+    /// The fingerprint doesn't have the length of an encoded position
+    InvalidLength,
+    /// The fingerprint contains a character outside of the base64url alphabet
+    InvalidCharacter,
+    /// The fingerprint decodes to a code that doesn't describe a reachable position
+    InvalidPosition,
+}
+
+impl Display for FingerprintError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FingerprintError::InvalidLength => write!(f, "invalid fingerprint length"),
+            FingerprintError::InvalidCharacter => write!(f, "invalid character in fingerprint"),
+            FingerprintError::InvalidPosition => write!(f, "fingerprint doesn't describe a valid position"),
+        }
+    }
+}
+
+impl core::error::Error for FingerprintError {}
+
+/// Error returned when creating a board from cells that don't describe a position, see [SizedBitBoard::from_grid()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InvalidGrid {
+    /// The cell holds a piece above an empty cell
+    FloatingPiece { row: usize, column: usize },
+    /// The numbers of pieces of each player don't allow `to_move` to play
+    PieceCount { one: usize, two: usize, to_move: Player },
+}
+
+impl Display for InvalidGrid {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidGrid::FloatingPiece { row, column } => {
+                write!(f, "the piece on row {row} of column {column} is above an empty cell")
+            }
+            InvalidGrid::PieceCount { one, two, to_move } => write!(
+                f,
+                "player One has {one} pieces and player Two {two}, so player {to_move:?} can't be the one to move"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for InvalidGrid {}
+
+// Alphabet of the base64url encoding used by fingerprints
+const FINGERPRINT_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+// Number of characters needed to encode the 8 bytes of a key with 6 bits per character
+const FINGERPRINT_LEN: usize = 11;
+
+/// A bitboard of W columns and H rows, where N pieces in a row win the game.
+///
+/// # Supported sizes
+/// The board can't be larger than the standard 7x6 board, which is checked at compile time:
+/// - W is at most [WIDTH], since columns are [Column]s.
+/// - W * H is at most 42: the history and the arrays of the solver are sized for the standard board, and
+///   the solver's scores are encoded within the bounds of its scores.
+/// - W * (H + 1) is at most 49: the keys of the positions have at most 49 bits, which the transposition table
+///   tells apart by their index and 32 stored bits.
+///
+/// So 6x5 or 4x4 boards are supported, but 8x7 boards, whose keys need 64 bits, aren't.
+///
+/// With the serde feature, a board is serialized as its stones and number of moves, without its history.
+/// Deserializing fails for stones that can't be reached by dropping pieces.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SerializedBitBoard", try_from = "SerializedBitBoard")
+)]
+pub struct SizedBitBoard<const W: usize, const H: usize, const N: usize = 4> {
+    n_moves: usize,
+    pos: BitBoardField, // stores the positions of the pieces of the current player
+    mask: BitBoardField, // marks all non-empty cells
+    first_player: Player, // player who made the first move, only player Two on some boards built from a grid
+    history: [Column; WIDTH * HEIGHT], // moves played since the board was created, the first history_len are valid
+    history_len: usize,
+}
+
+/// The bitboard of the standard 7x6 board
+pub type BitBoard = SizedBitBoard<WIDTH, HEIGHT>;
+
+/// Boards are equal when they have the same stones and the same player to move, whatever the moves that led
+/// to them: transpositions are equal, and the history is ignored. A position and its mirror image aren't equal.
+impl<const W: usize, const H: usize, const N: usize> PartialEq for SizedBitBoard<W, H, N> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.pos, self.mask, self.current_player()) == (other.pos, other.mask, other.current_player())
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Eq for SizedBitBoard<W, H, N> {}
+
+impl<const W: usize, const H: usize, const N: usize> core::hash::Hash for SizedBitBoard<W, H, N> {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        (self.pos, self.mask, self.current_player() as u8).hash(state);
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Default for SizedBitBoard<W, H, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+sa::const_assert!(core::mem::size_of::<BitBoardField>() <= (HEIGHT + 1) * WIDTH);
+
+impl<const W: usize, const H: usize, const N: usize> Board for SizedBitBoard<W, H, N> {
+    const WIDTH: usize = W;
+    const HEIGHT: usize = H;
+    const WIN_LENGTH: usize = N;
+
+    #[inline]
+    fn is_playable(&self, column: Column) -> bool {
+        (column as usize) < W && self.mask & Self::top_mask_col(column) == 0
+    }
+
+    #[inline]
+    fn is_winning(&self, column: Column) -> bool {
+        self.playable_squares_mask() & self.winning_position() & Self::column_mask(column) != 0
+    }
+
+    #[inline]
+    fn number_of_moves(&self) -> u32 {
+        self.n_moves as u32
+    }
+
+    // The players alternate from the first player, who isn't always player One
+    #[inline]
+    fn current_player(&self) -> Player {
+        if (self.n_moves + self.first_player as usize).is_multiple_of(2) {
+            Player::One
+        } else {
+            Player::Two
+        }
+    }
+
+    #[inline]
+    fn play(&mut self, column: Column) -> u32 {
+        let cell = (self.mask + Self::bottom_mask_col(column)) & Self::column_mask(column);
+        self.pos ^= self.mask; // switch player
+        self.mask |= cell; // play in the column
+
+        self.history[self.history_len] = column;
+        self.history_len += 1;
+        self.n_moves += 1;
+        self.n_moves as u32
+    }
+
+    #[inline]
+    fn unplay(&mut self, column: Column) -> u32 {
+        let column_cells = self.mask & Self::column_mask(column);
+        debug_assert!(column_cells != 0, "Called unplay on an empty column");
+
+        // The cells of a column are contiguous from the bottom, so this isolates the top one
+        let cell = (column_cells + Self::bottom_mask_col(column)) >> 1;
+        self.mask ^= cell;
+        self.pos ^= self.mask; // switch player back
+
+        self.n_moves -= 1;
+        if self.history_len > 0 {
+            debug_assert!(self.history[self.history_len - 1] == column, "Called unplay on another column than the last move");
+            self.history_len -= 1;
+        }
+        self.n_moves as u32
+    }
+
+    fn history(&self) -> &[Column] {
+        &self.history[..self.history_len]
+    }
+
+    // Only the valid part of the history needs to be cleared, which is done by its length
+    fn reset(&mut self) {
+        self.n_moves = 0;
+        self.pos = 0;
+        self.mask = 0;
+        self.first_player = Player::One;
+        self.history_len = 0;
+    }
+
+    #[inline]
+    fn key(&self) -> u64 {
+        self.pos + self.mask
+    }
+
+    #[inline]
+    fn canonical_key(&self) -> u64 {
+        let key = self.key();
+        key.min(Self::mirror(key))
+    }
+
+    // Computed from the stones when needed, since the solver only hashes boards without a key
+    fn zobrist_key(&self) -> u64 {
+        let mut key = if self.current_player() == Player::Two { ZOBRIST_TWO_TO_MOVE } else { 0 };
+        for (player, index) in [(Player::One, 0), (Player::Two, 1)] {
+            let mut stones = self.stones(player);
+            while stones != 0 {
+                key ^= ZOBRIST_VALUES[stones.trailing_zeros() as usize][index];
+                stones &= stones - 1;
+            }
+        }
+        key
+    }
+
+    fn status(&self) -> GameStatus {
+        if self.n_moves == 0 {
+            GameStatus::InProgress
+        } else if Self::has_alignment(self.pos ^ self.mask) {
+            let last_player = if self.current_player() == Player::One { Player::Two } else { Player::One };
+            GameStatus::Won(last_player)
+        } else if self.is_full() {
+            GameStatus::Drawn
+        } else {
+            GameStatus::InProgress
+        }
+    }
+
+    #[inline]
+    fn can_win_in_one_move(&self) -> bool {
+        self.playable_squares_mask() & self.winning_position() != 0
+    }
+
+    fn possible_nonlosing_moves(&self) -> BitBoardField {
+        debug_assert!(!self.can_win_in_one_move(),
+        "Called possible_nonlosing_moves but there is a move that immediately wins the game for the current player");
+        self.nonlosing_moves(self.playable_squares_mask())
+    }
+
+    // Computes the playable squares once for both checks
+    fn moves(&self) -> Moves {
+        let playable = self.playable_squares_mask();
+        let wins = playable & self.winning_position();
+        if wins != 0 {
+            let column = wins.trailing_zeros() as usize / (H + 1);
+            return Moves::ImmediateWin(Column::from_repr(column).unwrap());
+        }
+        match self.nonlosing_moves(playable) {
+            0 => Moves::Lost,
+            moves => Moves::NonLosing(moves),
+        }
+    }
+
+    // The score is the number of winning positions after the move
+    fn score_move(&self, column: Column) -> ScoredMove {
+        let move_bitmask = (self.mask + Self::bottom_mask_col(column)) & Self::column_mask(column);
+        let score = Self::compute_winning_position(self.pos | move_bitmask, self.mask).count_ones();
+        ScoredMove {
+            column,
+            score,
+        }
+    }
+
+    // The opponent's winning positions outside the mask only change in the cell of the move
+    fn score_move_detailed(&self, column: Column) -> DetailedMove {
+        let move_bitmask = (self.mask + Self::bottom_mask_col(column)) & Self::column_mask(column);
+        DetailedMove {
+            column,
+            threats_created: self.score_move(column).score,
+            threats_blocked: (self.opponent_winning_position() & move_bitmask).count_ones(),
+        }
+    }
+
+    fn landing_rows(&self) -> [Option<usize>; WIDTH] {
+        let playable = self.playable_squares_mask();
+        let mut rows = [None; WIDTH];
+        for column in Column::iter().take(W) {
+            let square = playable & Self::column_mask(column);
+            if square != 0 {
+                rows[column as usize] = Some(square.trailing_zeros() as usize - column as usize * (H + 1));
+            }
+        }
+        rows
+    }
+
+    fn cell(&self, row: usize, column: usize) -> Cell {
+        debug_assert!(row < H && column < W, "Cell ({row}, {column}) isn't on the board");
+        let square = 1 << (row + column * (H + 1));
+        if self.stones(Player::One) & square != 0 {
+            Cell::P1
+        } else if self.stones(Player::Two) & square != 0 {
+            Cell::P2
+        } else {
+            Cell::Empty
+        }
+    }
+
+    fn threats(&self, player: Player) -> u32 {
+        Self::compute_winning_position(self.stones(player), self.mask).count_ones()
+    }
+
+    fn creates_opponent_fork(&self, column: Column) -> bool {
+        if self.is_winning(column) {
+            return false;
+        }
+
+        let mut next = *self;
+        next.play(column);
+        (next.playable_squares_mask() & next.winning_position()).count_ones() >= 2
+    }
+
+    fn has_double_threat(&self) -> bool {
+        let winning = self.winning_position();
+        let playable = self.playable_squares_mask() & winning;
+        // Above the top row is the column's guard bit, which is never a winning square
+        playable.count_ones() >= 2 || (playable << 1) & winning != 0
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> SizedBitBoard<W, H, N> {
+    // Shifts to the next cell vertically, horizontally and along both diagonals
+    const DIRECTIONS: [usize; 4] = [1, H + 1, H, H + 2];
+    // 1 on the bottom row of each column
+    const BOTTOM_MASK: BitBoardField = Self::bottom(W, H);
+    // 1 on every cell of the board
+    const BOARD_MASK: BitBoardField = Self::BOTTOM_MASK * ((1 << H) - 1);
+    // 1 on every cell of the odd rows, counting rows from 1 at the bottom
+    const ODD_ROWS_MASK: BitBoardField = Self::BOTTOM_MASK * (0x5555_5555 & ((1 << H) - 1));
+
+    pub fn new() -> Self {
+        Self::from_fields(0, 0, 0, Player::One)
+    }
+
+    /// Creates a position from a sequence of moves, one column number or letter per move.
+    /// Panics if the sequence is invalid, see [Self::try_from_notation()].
+    pub fn from_notation(notation: &str) -> Self {
+        Self::try_from_notation(notation).unwrap()
+    }
+
+    /// Creates a position from a sequence of moves, one column number or letter per move.
+    /// Fails on the first character that isn't a column, or on the first move into a full column.
+    pub fn try_from_notation(notation: &str) -> Result<Self, NotationError> {
+        let mut board = Self::new();
+        for (index, character) in notation.char_indices() {
+            let column = Column::try_from(character).map_err(|_| NotationError::InvalidCharacter { character, index })?;
+            if !board.is_playable(column) {
+                return Err(NotationError::ColumnFull { column, index });
+            }
+            board.play(column);
+        }
+        Ok(board)
+    }
+
+    /// Creates a position from a sequence of moves like [Self::try_from_notation()], checking that it's a game
+    /// in progress. Fails on the first character that isn't a column, on the first move into a full column,
+    /// and on the first move that ends the game, whether other moves follow it or not.
+    pub fn from_notation_checked(notation: &str) -> Result<Self, GameError> {
+        let mut board = Self::new();
+        for (index, character) in notation.char_indices() {
+            let column = Column::try_from(character).map_err(|_| NotationError::InvalidCharacter { character, index })?;
+            if !board.is_playable(column) {
+                return Err(NotationError::ColumnFull { column, index }.into());
+            }
+            if board.is_winning(column) || board.n_moves + 1 == W * H {
+                return Err(GameError::GameOver { index });
+            }
+            board.play(column);
+        }
+        Ok(board)
+    }
+
+    /// Returns every position reachable from the empty board in exactly `depth` moves, one entry per move sequence.
+    /// Sequences are not extended past a winning move, so a game can only end on the last move.
+    pub fn positions_at_depth(depth: u32) -> Vec<Self> {
+        let mut positions = Vec::new();
+        Self::new().visit_sequences(depth, &mut |board| positions.push(*board));
+        positions
+    }
+
+    /// Maps the canonical key of every position reachable in exactly `depth` moves to the number of
+    /// move sequences reaching it. See [Self::positions_at_depth()] for which sequences are counted.
+    #[cfg(feature = "std")]
+    pub fn position_census(depth: u32) -> HashMap<u64, usize> {
+        let mut census = HashMap::new();
+        Self::new().visit_sequences(depth, &mut |board| {
+            *census.entry(board.canonical_key()).or_insert(0) += 1;
+        });
+        census
+    }
+
+    fn visit_sequences(&self, depth: u32, visit: &mut impl FnMut(&Self)) {
+        if depth == 0 {
+            visit(self);
+            return;
+        }
+
+        for column in Column::iter().take(W) {
+            if !self.is_playable(column) || (depth > 1 && self.is_winning(column)) {
+                continue;
+            }
+
+            let mut next = *self;
+            next.play(column);
+            next.visit_sequences(depth - 1, visit);
+        }
+    }
+
+    /// Counts the empty cells where the given player would complete a four-in-a-row, split by row parity.
+    /// Returns the number of threats on odd rows and on even rows, counting rows from 1 at the bottom.
+    pub fn threats_by_parity(&self, player: Player) -> (u32, u32) {
+        let threats = Self::compute_winning_position(self.stones(player), self.mask);
+        let odd = (threats & Self::ODD_ROWS_MASK).count_ones();
+        let even = (threats & !Self::ODD_ROWS_MASK).count_ones();
+        (odd, even)
+    }
+
+    /// Returns the number of empty cells that would complete a four-in-a-row for the current player,
+    /// minus the same number for the opponent. A positive value favors the current player.
+    pub fn threat_differential(&self) -> i32 {
+        self.winning_position().count_ones() as i32 - self.opponent_winning_position().count_ones() as i32
+    }
+
+    /// Evaluates which player the threat structure favors, based on the classic parity rules:
+    /// player One profits from threats on odd rows and player Two from threats on even rows.
+    /// Returns a player if they have such a threat and their opponent has none, and None otherwise.
+    /// This is a heuristic and doesn't take the order in which threats can be played into account.
+    pub fn even_threat_advantage(&self) -> Option<Player> {
+        let (one_odd, _) = self.threats_by_parity(Player::One);
+        let (_, two_even) = self.threats_by_parity(Player::Two);
+        match (one_odd > 0, two_even > 0) {
+            (true, false) => Some(Player::One),
+            (false, true) => Some(Player::Two),
+            _ => None,
+        }
+    }
+
+    /// Returns a bitmask of the empty cells that can no longer be part of a four-in-a-row for any player,
+    /// because every line through them already holds stones of both players.
+    pub fn dead_cells(&self) -> BitBoardField {
+        let empty = !self.mask & Self::BOARD_MASK;
+        let live = Self::open_lines_cells(self.pos | empty) | Self::open_lines_cells((self.pos ^ self.mask) | empty);
+        empty & !live
+    }
+
+    /// Checks if the position is drawn no matter how it is played, because neither player can get a four-in-a-row.
+    /// A player can't when no line is left for them, e.g. when every empty cell is dead. The player to move also
+    /// can't when every column has an even number of empty cells, and their stones hold no line together with the
+    /// lower cell of each pair of empty cells: their opponent can answer every move on top of it, and gets the
+    /// upper cells. This refutes their threats on the upper cells, e.g. on the even rows of the standard board
+    /// counted by [Self::threats_by_parity()], but not the lines that need several lower cells.
+    /// This is conservative: when it returns false, the position may still be a draw.
+    pub fn is_theoretical_draw(&self) -> bool {
+        let empty = !self.mask & Self::BOARD_MASK;
+        let no_lines = |stones| Self::open_lines_cells(stones | empty) == 0;
+        // The rows with the parity of the height, which hold the lower cells when the empty cells pair up
+        let lower_rows = if H.is_multiple_of(2) {
+            Self::ODD_ROWS_MASK
+        } else {
+            Self::BOARD_MASK & !Self::ODD_ROWS_MASK
+        };
+        let paired = (self.mask + Self::BOTTOM_MASK) & !(lower_rows | Self::BOTTOM_MASK << H) == 0;
+        let refuted = paired && !Self::has_alignment(self.pos | (empty & lower_rows));
+        (no_lines(self.pos) || refuted) && no_lines(self.pos ^ self.mask)
+    }
+
+    /// Returns a fixed-length textual fingerprint of the position, for logging and copy-pasting.
+    /// It is the unpadded base64url encoding of the big-endian bytes of [Board::key()], so positions
+    /// reached by different move orders share the same fingerprint.
+    pub fn fingerprint(&self) -> String {
+        // 64 bits of key followed by 2 padding bits, 6 bits per character
+        let bits = (self.key() as u128) << 2;
+        (0..FINGERPRINT_LEN)
+            .rev()
+            .map(|i| FINGERPRINT_ALPHABET[((bits >> (6 * i)) & 0x3f) as usize] as char)
+            .collect()
+    }
+
+    /// Decodes a position from a fingerprint created by [Self::fingerprint()].
+    /// Fails if the fingerprint is malformed or if the stones it describes don't fit the board.
+    pub fn from_fingerprint(fingerprint: &str) -> Result<Self, FingerprintError> {
+        if fingerprint.len() != FINGERPRINT_LEN {
+            return Err(FingerprintError::InvalidLength);
+        }
+
+        let mut bits: u128 = 0;
+        for c in fingerprint.bytes() {
+            let value = FINGERPRINT_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or(FingerprintError::InvalidCharacter)?;
+            bits = bits << 6 | value as u128;
+        }
+        if bits & 0b11 != 0 {
+            return Err(FingerprintError::InvalidPosition);
+        }
+
+        Self::from_key((bits >> 2) as BitBoardField).ok_or(FingerprintError::InvalidPosition)
+    }
+
+    /// Creates a board from the content of its cells, by row (0 being the bottom, as in [Board::cell()]) then by
+    /// column, with `to_move` to play. Unlike [Self::from_notation()], the position doesn't need to be reached by
+    /// a sequence of moves, e.g. the game may already be won, and the board has no history.
+    /// Either player may have made the first move: when both players have as many pieces, it was `to_move`,
+    /// and otherwise the player with one more piece, who mustn't be `to_move`.
+    /// [Board::current_player()] is then `to_move`, and the players alternate from there whatever the parity of
+    /// [Board::number_of_moves()]. Fails if a piece is above an empty cell, or if the pieces don't allow `to_move`
+    /// to play.
+    pub fn from_grid(cells: [[Cell; W]; H], to_move: Player) -> Result<Self, InvalidGrid> {
+        let (mut one, mut two) = (0, 0);
+        let (mut stones_one, mut mask) = (0, 0);
+        for (row, cells_row) in cells.iter().enumerate() {
+            for (column, cell) in cells_row.iter().enumerate() {
+                let square: BitBoardField = 1 << (row + column * (H + 1));
+                match cell {
+                    Cell::Empty => continue,
+                    Cell::P1 => {
+                        one += 1;
+                        stones_one |= square;
+                    }
+                    Cell::P2 => two += 1,
+                }
+                if row > 0 && cells[row - 1][column] == Cell::Empty {
+                    return Err(InvalidGrid::FloatingPiece { row, column });
+                }
+                mask |= square;
+            }
+        }
+
+        let first_player = match to_move {
+            _ if one == two => to_move,
+            Player::Two if one == two + 1 => Player::One,
+            Player::One if one + 1 == two => Player::Two,
+            _ => return Err(InvalidGrid::PieceCount { one, two, to_move }),
+        };
+        let pos = if to_move == Player::One { stones_one } else { stones_one ^ mask };
+        Ok(Self::from_fields(one + two, pos, mask, first_player))
+    }
+
+    // Rebuilds a position from its key. In each column the key holds the current player's stones plus
+    // the column mask, so the highest set bit of the column block plus one is right above the top stone.
+    fn from_key(key: BitBoardField) -> Option<Self> {
+        let block_mask: BitBoardField = (1 << (H + 1)) - 1;
+        if key >> (W * (H + 1)) != 0 {
+            return None;
+        }
+
+        let mut pos = 0;
+        let mut mask = 0;
+        for column in 0..W {
+            let block = ((key >> (column * (H + 1))) & block_mask) + 1;
+            let height = BitBoardField::BITS - 1 - block.leading_zeros();
+            if height as usize > H {
+                return None;
+            }
+
+            let column_mask = (1 << height) - 1;
+            pos |= (block - 1 - column_mask) << (column * (H + 1));
+            mask |= column_mask << (column * (H + 1));
+        }
+
+        // The current player has played exactly half of the moves, rounded down
+        let n_moves = mask.count_ones() as usize;
+        if pos.count_ones() as usize != n_moves / 2 {
+            return None;
+        }
+
+        Some(Self::from_fields(n_moves, pos, mask, Player::One))
+    }
+
+    /// Returns the player who made the first move: player One, unless the board was built by [Self::from_grid()]
+    /// with player Two to start
+    pub fn first_player(&self) -> Player {
+        self.first_player
+    }
+
+    // Returns the position with the same stones and the other player to move, as if the current player
+    // had passed. This isn't a legal move, and the returned position isn't reachable in a real game.
+    pub(crate) fn null_move(&self) -> Self {
+        let first_player = if self.first_player == Player::One { Player::Two } else { Player::One };
+        Self::from_fields(self.n_moves, self.pos ^ self.mask, self.mask, first_player)
+    }
+
+    // Checks if the bottom stone of the column belongs to the current player, who can pop it out in Pop Out
+    pub(super) fn can_pop(&self, column: Column) -> bool {
+        (column as usize) < W && self.pos & Self::bottom_mask_col(column) != 0
+    }
+
+    // Removes the bottom stone of the column, which must belong to the current player, shifting the column
+    // down, and passes the turn. The number of moves stays the number of stones, whose parity then still gives
+    // the player to move. The history restarts, since a pop isn't a column of a move sequence
+    pub(super) fn pop(&mut self, column: Column) {
+        debug_assert!(self.can_pop(column), "Called pop on a column without a stone of the current player at the bottom");
+        let column_mask = Self::column_mask(column);
+        // The bit shifted out of the bottom lands in the previous column's sentinel row, and is masked out
+        let shift_down = |field: BitBoardField| field & !column_mask | (field & column_mask) >> 1 & column_mask;
+        let pos = shift_down(self.pos);
+        let mask = shift_down(self.mask);
+        *self = Self::from_fields(self.n_moves - 1, pos ^ mask, mask, self.first_player);
+    }
+
+    // Reverses a pop in the column by the previous player
+    pub(super) fn unpop(&mut self, column: Column) {
+        let column_mask = Self::column_mask(column);
+        let bottom = Self::bottom_mask_col(column);
+        debug_assert!(self.mask & Self::top_mask_col(column) == 0, "Called unpop on a full column");
+        let shift_up = |field: BitBoardField| field & !column_mask | (field & column_mask) << 1;
+        let pos = shift_up(self.pos ^ self.mask) | bottom;
+        let mask = shift_up(self.mask) | bottom;
+        *self = Self::from_fields(self.n_moves + 1, pos, mask, self.first_player);
+    }
+
+    // Returns whether the current player and the opponent have a four-in-a-row
+    pub(super) fn alignments(&self) -> (bool, bool) {
+        (Self::has_alignment(self.pos), Self::has_alignment(self.pos ^ self.mask))
+    }
+
+    // Creates a board from its fields. Its history is empty
+    fn from_fields(n_moves: usize, pos: BitBoardField, mask: BitBoardField, first_player: Player) -> Self {
+        // Every board is created here, so this rejects unsupported sizes at compile time
+        const {
+            assert!(
+                W <= WIDTH && W * H <= WIDTH * HEIGHT && W * (H + 1) <= WIDTH * (HEIGHT + 1),
+                "The board can't be larger than the standard board"
+            );
+            assert!(N >= 2, "At least two pieces in a row must be needed to win")
+        };
+        Self {
+            n_moves,
+            pos,
+            mask,
+            first_player,
+            history: [Column::A; WIDTH * HEIGHT],
+            history_len: 0,
+        }
+    }
+
+    // Returns the stones of the given player
+    fn stones(&self, player: Player) -> BitBoardField {
+        if player == self.current_player() {
+            self.pos
+        } else {
+            self.pos ^ self.mask
+        }
+    }
+
+    #[inline]
+    fn bottom_mask_col(column: Column) -> BitBoardField {
+        1 << (column as usize * (H + 1))
+    }
+
+    #[inline]
+    pub fn column_mask(column: Column) -> BitBoardField {
+        ((1 << H) - 1) << (column as usize * (H + 1))
+    }
+
+    #[inline]
+    fn top_mask_col(column: Column) -> BitBoardField {
+        1 << (H - 1) << (column as usize * (H + 1))
+    }
+
+    /// Returns a bitmask with one bit set for each playable column, on the cell where the next piece
+    /// would land. Cell (row, column) is bit `column * (H + 1) + row`, with row 0 at the bottom.
+    #[inline]
+    pub fn playable_squares_mask(&self) -> BitBoardField {
+        (self.mask + Self::BOTTOM_MASK) & Self::BOARD_MASK
+    }
+
+    // Returns a bitmask of the possible winning moves for the current player
+    fn winning_position(&self) -> BitBoardField {
+        Self::compute_winning_position(self.pos, self.mask)
+    }
+
+    // Returns a bitmask of the possible winning moves for the opponent
+    fn opponent_winning_position(&self) -> BitBoardField {
+        Self::compute_winning_position(self.pos ^ self.mask, self.mask)
+    }
+
+    // Returns the playable squares that don't let the opponent win next move, ignoring the current player's wins
+    fn nonlosing_moves(&self, mut possible: BitBoardField) -> BitBoardField {
+        let opponent_win = self.opponent_winning_position();
+        let forced_moves = possible & opponent_win;
+        if forced_moves != 0 {
+            if forced_moves & (forced_moves - 1) != 0 {
+                // more than one forced move, we can't do anything
+                return 0;
+            }
+
+            possible = forced_moves;
+        }
+
+        // Don't play directly under an opponent's winning position as well
+        possible & !(opponent_win >> 1)
+    }
+
+    // Mirrors a bitmask horizontally by reversing the order of the column blocks
+    const fn mirror(field: BitBoardField) -> BitBoardField {
+        let block_mask: BitBoardField = (1 << (H + 1)) - 1;
+        let mut mirrored = 0;
+        let mut column = 0;
+        while column < W {
+            let block = (field >> (column * (H + 1))) & block_mask;
+            mirrored |= block << ((W - 1 - column) * (H + 1));
+            column += 1;
+        }
+        mirrored
+    }
+
+    // Returns a bitmask of the cells belonging to a line of N cells available to a player, given the cells
+    // that are either empty or hold their stones. The empty row on top of the board prevents wrapping lines.
+    const fn open_lines_cells(available: BitBoardField) -> BitBoardField {
+        let mut cells = 0;
+        let mut d = 0;
+        while d < Self::DIRECTIONS.len() {
+            let shift = Self::DIRECTIONS[d];
+            let mut starts = available; // bottom-left cell of each line of N
+            let mut i = 1;
+            while i < N {
+                starts &= available >> (i * shift);
+                i += 1;
+            }
+            let mut i = 0;
+            while i < N {
+                cells |= starts << (i * shift);
+                i += 1;
+            }
+            d += 1;
+        }
+        cells
+    }
+
+    // Recursively construct a bitmask with 1 on the bottom row of each column
+    const fn bottom(width: usize, height: usize) -> BitBoardField {
+        if width == 0 {
+            0
+        } else {
+            1 << ((height + 1) * (width - 1)) | Self::bottom(width - 1, height)
+        }
+    }
+
+    // Checks if the stones of a player contain N in a row
+    const fn has_alignment(stones: BitBoardField) -> bool {
+        let mut d = 0;
+        while d < Self::DIRECTIONS.len() {
+            let mut line = stones;
+            let mut i = 1;
+            while i < N {
+                line &= stones >> (i * Self::DIRECTIONS[d]);
+                i += 1;
+            }
+            if line != 0 {
+                return true;
+            }
+            d += 1;
+        }
+        false
+    }
+
+    // Returns a bitmask of the possible winning moves for the current position (player) and mask
+    const fn compute_winning_position(
+        position: BitBoardField,
+        mask: BitBoardField,
+    ) -> BitBoardField {
+        // Resulting bitmask is the actual move, because of the shifts
+        // Vertically, the N - 1 stones can only be below the move
+        let mut moves = position << 1;
+        let mut i = 2;
+        while i < N {
+            moves &= position << i;
+            i += 1;
+        }
+
+        // In the other directions, a move wins with `left` stones to its left and N - 1 - left to its right.
+        // before[i] (after[i]) marks the cells with i stones right before (after) them in the direction
+        let mut d = 1;
+        while d < Self::DIRECTIONS.len() {
+            let shift = Self::DIRECTIONS[d];
+            let mut before = [!0; N];
+            let mut after = [!0; N];
+            let mut i = 1;
+            while i < N {
+                before[i] = before[i - 1] & (position << (i * shift));
+                after[i] = after[i - 1] & (position >> (i * shift));
+                i += 1;
+            }
+            let mut left = 0;
+            while left < N {
+                moves |= before[left] & after[N - 1 - left];
+                left += 1;
+            }
+            d += 1;
+        }
+
+        moves & (mask ^ Self::BOARD_MASK)
+    }
+}
+
+// The fields a bitboard is serialized as
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedBitBoard {
+    pos: BitBoardField,
+    mask: BitBoardField,
+    n_moves: u32,
+    // only serialized when set, see SizedBitBoard::first_player()
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    two_first: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<const W: usize, const H: usize, const N: usize> From<SizedBitBoard<W, H, N>> for SerializedBitBoard {
+    fn from(board: SizedBitBoard<W, H, N>) -> Self {
+        Self {
+            pos: board.pos,
+            mask: board.mask,
+            n_moves: board.n_moves as u32,
+            two_first: board.first_player == Player::Two,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const W: usize, const H: usize, const N: usize> TryFrom<SerializedBitBoard> for SizedBitBoard<W, H, N> {
+    type Error = &'static str;
+
+    fn try_from(fields: SerializedBitBoard) -> Result<Self, Self::Error> {
+        let SerializedBitBoard { pos, mask, n_moves, two_first } = fields;
+        // Stones must be on the board, with no gap below them, and the current player must have played
+        // half of the moves, rounded down
+        if mask & !Self::BOARD_MASK != 0 || (mask + Self::BOTTOM_MASK) & mask != 0 || pos & !mask != 0 {
+            return Err("the stones aren't stacked from the bottom of the board");
+        }
+        if mask.count_ones() != n_moves || pos.count_ones() != n_moves / 2 {
+            return Err("the number of moves doesn't match the stones");
+        }
+        let first_player = if two_first { Player::Two } else { Player::One };
+        Ok(Self::from_fields(n_moves as usize, pos, mask, first_player))
+    }
+}
+
+/// Parses a sequence of moves like [SizedBitBoard::try_from_notation()], e.g. `"435462".parse::<BitBoard>()`
+impl<const W: usize, const H: usize, const N: usize> core::str::FromStr for SizedBitBoard<W, H, N> {
+    type Err = NotationError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        Self::try_from_notation(notation)
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Display for SizedBitBoard<W, H, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = String::new();
+        for row in (0..H).rev() {
+            for column in 0..W {
+                let pos = 1 << (row + column * (H + 1));
+                let is_stone = self.mask & pos != 0;
+                let is_stone_current_player = self.pos & pos != 0;
+                let is_p1 = self.current_player() == Player::One;
+
+                if is_stone {
+                    if is_stone_current_player {
+                        s.push(if is_p1 { 'X' } else { 'O' });
+                    } else {
+                        s.push(if is_p1 { 'O' } else { 'X' });
+                    }
+                } else {
+                    s.push('-');
+                }
+            }
+            s.push('\n');
+        }
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::collections::BinaryHeap;
+    use alloc::string::ToString;
+    #[cfg(feature = "std")]
+    use rand::rngs::StdRng;
+    #[cfg(feature = "std")]
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_is_playable() {
+        let mut board = BitBoard::new();
+        Column::iter().for_each(|c| assert!(board.is_playable(c)));
+
+        for _ in 0..HEIGHT {
+            board.play(Column::A);
+        }
+
+        assert!(!board.is_playable(Column::A));
+    }
+
+    #[test]
+    fn test_is_winning_horizontal() {
+        let board = BitBoard::from_notation("435462");
+        assert!(board.is_winning(Column::G));
+        assert!(board.can_win_in_one_move());
+        Column::iter()
+            .filter(|&c| c != Column::G)
+            .for_each(|c| assert!(!board.is_winning(c)));
+    }
+
+    #[test]
+    fn test_is_winning_vertical() {
+        let board = BitBoard::from_notation("123451121517");
+        assert!(board.is_winning(Column::A));
+        assert!(board.can_win_in_one_move());
+        Column::iter()
+            .skip(1)
+            .for_each(|c| assert!(!board.is_winning(c)));
+    }
+
+    #[test]
+    fn test_is_winning_diagonal() {
+        let board = BitBoard::from_notation("453433222");
+        assert!(board.is_winning(Column::B));
+        assert!(board.can_win_in_one_move());
+
+        let board = BitBoard::from_notation("2334454551");
+        assert!(board.is_winning(Column::E));
+        assert!(board.can_win_in_one_move());
+    }
+
+    #[test]
+    fn test_is_winning_2_places() {
+        let board = BitBoard::from_notation("445362322111");
+        assert!(board.is_winning(Column::A)); // diagonal win
+        assert!(board.is_winning(Column::G)); // horizontal win
+        assert!(board.can_win_in_one_move());
+
+        Column::iter()
+            .filter(|&c| c != Column::G && c != Column::A)
+            .for_each(|c| assert!(!board.is_winning(c)));
+    }
+
+    #[test]
+    fn test_play() {
+        let mut board = BitBoard::new();
+        assert_eq!(board.play(Column::D), 1);
+        assert_eq!(board.play(Column::E), 2);
+        assert_eq!(board.play(Column::D), 3);
+        assert_eq!(board.play(Column::G), 4);
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(BitBoard::new().status(), GameStatus::InProgress);
+        assert_eq!(BitBoard::from_notation("445566").status(), GameStatus::InProgress);
+        assert_eq!(BitBoard::from_notation("4455667").status(), GameStatus::Won(Player::One));
+        assert_eq!(BitBoard::from_notation("14455667").status(), GameStatus::Won(Player::Two));
+        // Diagonal
+        assert_eq!(BitBoard::from_notation("12234334544").status(), GameStatus::Won(Player::One));
+
+        let full = "675424577317223371452513267643443266156151";
+        assert_eq!(BitBoard::from_notation(&full[..41]).status(), GameStatus::InProgress);
+        assert_eq!(BitBoard::from_notation(full).status(), GameStatus::Drawn);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut board = BitBoard::from_notation("4453");
+        assert_eq!(board.history(), [Column::D, Column::D, Column::E, Column::C]);
+        assert_eq!(board.to_notation(), "4453");
+
+        board.play(Column::G);
+        assert_eq!(board.to_notation(), "44537");
+        board.unplay(Column::G);
+        board.unplay(Column::C);
+        assert_eq!(board.to_notation(), "445");
+        assert_eq!(BitBoard::from_notation("abc").to_notation(), "123");
+
+        // Boards that aren't created by playing moves only remember the moves played since
+        let mut board = BitBoard::from_fingerprint(&board.fingerprint()).unwrap();
+        assert!(board.history().is_empty());
+        board.play(Column::A);
+        assert_eq!(board.to_notation(), "1");
+    }
+
+    #[test]
+    fn test_cell() {
+        let board = BitBoard::from_notation("4453");
+        assert_eq!(board.cell(0, 3), Cell::P1);
+        assert_eq!(board.cell(1, 3), Cell::P2);
+        assert_eq!(board.cell(0, 4), Cell::P1);
+        assert_eq!(board.cell(0, 2), Cell::P2);
+        assert_eq!(board.cell(2, 3), Cell::Empty);
+        assert_eq!(board.cell(HEIGHT - 1, WIDTH - 1), Cell::Empty);
+
+        // The cells don't depend on the player to move
+        let board = SizedBitBoard::<5, 4>::from_notation("11");
+        assert_eq!((board.cell(0, 0), board.cell(1, 0), board.cell(3, 4)), (Cell::P1, Cell::P2, Cell::Empty));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn test_cell_out_of_bounds() {
+        SizedBitBoard::<5, 4>::new().cell(0, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_eq_hash() {
+        let board = BitBoard::from_notation("4453");
+        let transposition = BitBoard::from_notation("4354");
+        assert!(board == transposition);
+        // The mirror image differs, as do the same stones with the other player to move
+        assert!(board != BitBoard::from_notation("4435"));
+        assert!(board != board.null_move());
+
+        let set: std::collections::HashSet<_> = [board, transposition, BitBoard::new()].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        assert_eq!(BitBoard::new().legal_moves(), [true; WIDTH]);
+        let board = BitBoard::from_notation("444444");
+        assert_eq!(board.legal_moves(), [true, true, true, false, true, true, true]);
+
+        // Full and won boards don't panic
+        let full = BitBoard::from_notation("675424577317223371452513267643443266156151");
+        assert_eq!(full.legal_moves(), [false; WIDTH]);
+        let won = BitBoard::from_notation("4455667");
+        assert_eq!(won.legal_moves(), [true; WIDTH]);
+        assert_eq!(SizedBitBoard::<5, 4>::new().legal_moves(), [true, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_current_player() {
+        assert_eq!(BitBoard::new().current_player(), Player::One);
+        assert_eq!(BitBoard::from_notation("4").current_player(), Player::Two);
+        assert_eq!(BitBoard::from_notation("44").current_player(), Player::One);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut board = BitBoard::from_notation("4453");
+        board.reset();
+        assert_eq!(board.number_of_moves(), 0);
+        assert_eq!(board.key(), BitBoard::new().key());
+        assert_eq!(board.zobrist_key(), BitBoard::new().zobrist_key());
+        assert!(board.history().is_empty());
+        board.play(Column::A);
+        assert_eq!(board.to_notation(), "1");
+    }
+
+    #[test]
+    fn test_from_notation_checked() {
+        let board = BitBoard::from_notation_checked("2252576253462244111563365343671351441").unwrap();
+        assert_eq!(board.to_notation(), "2252576253462244111563365343671351441");
+
+        // Six pieces fit in a column, the seventh doesn't
+        assert!(BitBoard::from_notation_checked("444444").is_ok());
+        assert_eq!(
+            BitBoard::from_notation_checked("4444444").err(),
+            Some(GameError::Notation(NotationError::ColumnFull { column: Column::D, index: 6 }))
+        );
+        assert_eq!(
+            BitBoard::from_notation_checked("44x").err(),
+            Some(GameError::Notation(NotationError::InvalidCharacter { character: 'x', index: 2 }))
+        );
+
+        // Player One wins with the seventh move
+        assert!(BitBoard::from_notation_checked("445566").is_ok());
+        assert_eq!(BitBoard::from_notation_checked("4455667").err(), Some(GameError::GameOver { index: 6 }));
+        assert_eq!(BitBoard::from_notation_checked("44556671").err(), Some(GameError::GameOver { index: 6 }));
+
+        // Filling the board without a win also ends the game
+        assert!(SizedBitBoard::<2, 2>::from_notation_checked("112").is_ok());
+        assert_eq!(SizedBitBoard::<2, 2>::from_notation_checked("1122").err(), Some(GameError::GameOver { index: 3 }));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_notation_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut notation = String::new();
+            let mut board = BitBoard::new();
+            while let Some(column) = board.random_move(&mut rng) {
+                board.play(column);
+                notation.push(char::from(column));
+                assert_eq!(BitBoard::from_notation(&notation).to_notation(), notation);
+            }
+            assert_eq!(board.to_notation(), notation);
+        }
+    }
+
+    #[test]
+    fn test_connect_n() {
+        // Four in a row isn't enough to win Connect 5
+        let mut board = SizedBitBoard::<WIDTH, HEIGHT, 5>::from_notation("1717171");
+        assert_eq!(board.status(), GameStatus::InProgress);
+        assert!(!board.is_winning(Column::G));
+        board.play(Column::G);
+        assert_eq!(board.status(), GameStatus::InProgress);
+        assert!(board.is_winning(Column::A));
+        board.play(Column::A);
+        assert_eq!(board.status(), GameStatus::Won(Player::One));
+
+        // The winning cell can be inside the line, linking the stones on both sides
+        let board = SizedBitBoard::<WIDTH, HEIGHT, 5>::from_notation("1122445566");
+        assert!(board.is_winning(Column::C));
+        assert!(!BitBoard::from_notation("112244").is_winning(Column::E));
+        // Diagonally in Connect 3
+        let board = SizedBitBoard::<WIDTH, HEIGHT, 3>::from_notation("122343");
+        assert!(board.is_winning(Column::C));
+        assert!(!board.is_winning(Column::D));
+    }
+
+    #[test]
+    fn test_playable_squares() {
+        let mut board = BitBoard::from_notation("1112234");
+        for _ in 0..HEIGHT - 3 {
+            board.play(Column::A);
+        }
+
+        assert_eq!(
+            board.landing_rows(),
+            [None, Some(2), Some(1), Some(1), Some(0), Some(0), Some(0)]
+        );
+
+        let playable = board.playable_squares_mask();
+        assert_eq!(playable.count_ones(), WIDTH as u32 - 1);
+        assert_eq!(playable & BitBoard::column_mask(Column::A), 0);
+        assert_eq!(playable & BitBoard::column_mask(Column::B), 1 << (HEIGHT + 1 + 2));
+        assert_eq!(playable & BitBoard::column_mask(Column::E), 1 << (4 * (HEIGHT + 1)));
+    }
+
+    #[test]
+    fn test_unplay() {
+        let mut board = BitBoard::from_notation("4453");
+        let key = board.key();
+
+        board.play(Column::D);
+        assert_eq!(board.unplay(Column::D), 4);
+        assert_eq!(board.key(), key);
+
+        assert_eq!(board.unplay(Column::C), 3);
+        assert_eq!(board.key(), BitBoard::from_notation("445").key());
+    }
+
+    #[test]
+    fn test_try_from_notation() {
+        let board = BitBoard::try_from_notation("4453").unwrap();
+        assert_eq!(board.key(), BitBoard::from_notation("dDe3").key());
+        assert_eq!(BitBoard::try_from_notation("").unwrap().key(), BitBoard::new().key());
+
+        assert_eq!(
+            BitBoard::try_from_notation("12x4").err(),
+            Some(NotationError::InvalidCharacter { character: 'x', index: 2 })
+        );
+        assert_eq!(
+            BitBoard::try_from_notation("1111111").err(),
+            Some(NotationError::ColumnFull { column: Column::A, index: 6 })
+        );
+
+        assert_eq!("4453".parse::<BitBoard>().unwrap().history(), board.history());
+        assert_eq!("12x4".parse::<BitBoard>().err(), Some(NotationError::InvalidCharacter { character: 'x', index: 2 }));
+        assert_eq!(
+            "11111".parse::<SizedBitBoard<4, 4>>().err(),
+            Some(NotationError::ColumnFull { column: Column::A, index: 4 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_unplay_random_sequence() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut board = BitBoard::new();
+            let mut moves = Vec::new();
+            let mut keys = Vec::new();
+            while let Some(column) = board.random_move(&mut rng) {
+                keys.push((board.key(), board.zobrist_key()));
+                board.play(column);
+                moves.push(column);
+            }
+
+            while let Some(column) = moves.pop() {
+                board.unplay(column);
+                assert_eq!(board.number_of_moves() as usize, moves.len());
+                assert_eq!((board.key(), board.zobrist_key()), keys.pop().unwrap());
+            }
+            assert_eq!(board.key(), BitBoard::new().key());
+            assert_eq!(board.zobrist_key(), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_random_playout() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // Only one cell left, filling it doesn't win so the game is drawn
+        let board = BitBoard::from_notation("67542457731722337145251326764344326615615");
+        assert_eq!(board.random_move(&mut rng), Some(Column::A));
+        assert_eq!(board.random_playout(&mut rng), 0);
+
+        let mut full = board;
+        full.play(Column::A);
+        assert_eq!(full.random_move(&mut rng), None);
+        assert_eq!(full.random_playout(&mut rng), 0);
+
+        // Only one cell left, and filling it wins for the current player
+        let board = BitBoard::from_notation("55722526747744416255216414711237533661363");
+        assert_eq!(board.random_playout(&mut rng), 1);
+    }
+
+    #[test]
+    fn test_possible_nonlosing_moves() {
+        let board = BitBoard::new();
+        let moves = board.possible_nonlosing_moves();
+        assert!(Column::iter().all(|c| moves & BitBoard::column_mask(c) != 0)); // all columns are possible
+
+        // Losing position
+        let board = BitBoard::from_notation("4453623221115");
+
+        // Player 2's turn, but player 1 can win in A or G. So there's nothing player 2 can do
+        assert_eq!(board.possible_nonlosing_moves(), 0); // all columns are losing
+
+        // Player 1 can win in E
+        let mut board = BitBoard::from_notation("2334465545");
+        assert!(board.is_winning(Column::E));
+        board.play(Column::A); // don't win yet
+        assert!(board.possible_nonlosing_moves() & BitBoard::column_mask(Column::E) != 0); // only E is possible otherwise p1 wins
+        assert!(Column::iter()
+            .filter(|&c| c != Column::E)
+            .all(|c| board.possible_nonlosing_moves() & BitBoard::column_mask(c) == 0)); // other columns are losing
+    }
+
+    #[test]
+    fn test_moves() {
+        let board = BitBoard::new();
+        assert_eq!(board.moves(), Moves::NonLosing(board.possible_nonlosing_moves()));
+        assert_eq!(BitBoard::from_notation("4453623221115").moves(), Moves::Lost);
+        let mut board = BitBoard::from_notation("2334465545");
+        assert_eq!(board.moves(), Moves::ImmediateWin(Column::E));
+        board.play(Column::A);
+        assert_eq!(board.moves(), Moves::NonLosing(BitBoard::column_mask(Column::E) & board.playable_squares_mask()));
+
+        // Positions with a win don't panic, and report the leftmost winning column
+        assert_eq!(BitBoard::from_notation("445566").moves(), Moves::ImmediateWin(Column::C));
+    }
+
+    #[test]
+    fn test_move_scoring() {
+        // A move with higher score is a move that creates possible wins by forming a connected 3 line
+        let board = BitBoard::from_notation("4655");
+
+        // Playing C will create a possible win in the next move, so it's score should be 1
+        assert_eq!(board.score_move(Column::C).score, 1);
+
+        let board = BitBoard::from_notation("465556677141");
+        // Playing C allows 2 wins in the next move (by playing eiter B or F)
+        // Additionally, playing F also allows 2 wins (in C or G, although G is not yet possible)
+        // or B allows 1 win in the next move by playing C
+        assert_eq!(board.score_move(Column::C).score, 2);
+        assert_eq!(board.score_move(Column::F).score, 2);
+        assert_eq!(board.score_move(Column::B).score, 1);
+    }
+
+    #[test]
+    fn test_move_scoring_detailed() {
+        // X has A1, B1 and C1 and O has E1 and F1, so O must play D1
+        let board = BitBoard::from_notation("15263");
+        // which blocks the win of X and lines up D1, E1 and F1 for a win in G1
+        let scored_move = board.score_move_detailed(Column::D);
+        assert_eq!(
+            scored_move,
+            DetailedMove { column: Column::D, threats_created: 1, threats_blocked: 1 }
+        );
+        assert_eq!(scored_move.threats_created, board.score_move(Column::D).score);
+        // G1 creates the same threat in D1 without blocking
+        assert_eq!(
+            board.score_move_detailed(Column::G),
+            DetailedMove { column: Column::G, threats_created: 1, threats_blocked: 0 }
+        );
+        // After D1, X can only block in G1
+        let board = BitBoard::from_notation("152634");
+        assert_eq!(board.score_move_detailed(Column::G).threats_blocked, 1);
+        assert_eq!(board.score_move_detailed(Column::A).threats_blocked, 0);
+    }
+
+    #[test]
+    fn move_sorting_with_heap() {
+        let scored_move = |column, score| ScoredMove { column, score };
+        let moves = [
+            scored_move(Column::A, 1),
+            scored_move(Column::B, 2),
+            scored_move(Column::C, 2),
+            scored_move(Column::D, 1),
+            scored_move(Column::E, 1),
+        ];
+
+        // Higher scores come first, then the moves with the same score from the center out,
+        // whatever the order they were pushed in
+        let expected = [Column::C, Column::B, Column::D, Column::E, Column::A];
+        for rotation in 0..moves.len() {
+            for reverse in [false, true] {
+                let mut moves = moves;
+                moves.rotate_left(rotation);
+                if reverse {
+                    moves.reverse();
+                }
+                let mut heap = BinaryHeap::from(moves.to_vec());
+                let popped: Vec<Column> = core::iter::from_fn(|| heap.pop()).map(|m| m.column).collect();
+                assert_eq!(popped, expected);
+            }
+        }
+
+        assert!(scored_move(Column::D, 1) > scored_move(Column::E, 1));
+        assert!(scored_move(Column::E, 1) > scored_move(Column::C, 1));
+        assert!(scored_move(Column::A, 2) > scored_move(Column::D, 1));
+        assert_eq!(scored_move(Column::A, 1).cmp(&scored_move(Column::A, 1)), core::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let board = BitBoard::from_notation("1");
+        let mirror = BitBoard::from_notation("7");
+        assert_ne!(board.key(), mirror.key());
+        assert_eq!(board.canonical_key(), mirror.canonical_key());
+
+        let board = BitBoard::from_notation("4");
+        assert_eq!(board.canonical_key(), board.key());
+
+        // "44" is its own mirror image
+        let board = BitBoard::from_notation("44");
+        assert_eq!(BitBoard::mirror(board.key()), board.key());
+        assert_eq!(board.canonical_key(), board.key());
+
+        let board = BitBoard::from_notation("4431");
+        let mirror = BitBoard::from_notation("4457");
+        assert_eq!(BitBoard::mirror(board.key()), mirror.key());
+        assert_eq!(board.canonical_key(), mirror.canonical_key());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_zobrist_key() {
+        let board = BitBoard::from_notation("4453");
+        assert_ne!(board.zobrist_key(), BitBoard::from_notation("4435").zobrist_key());
+        assert_eq!(board.zobrist_key(), BitBoard::from_notation("4354").zobrist_key());
+        assert_eq!(board.null_move().null_move().zobrist_key(), board.zobrist_key());
+        assert_eq!(BitBoard::from_key(board.key()).unwrap().zobrist_key(), board.zobrist_key());
+
+        // Distinct random positions, compared on the index in a default table and on the 32 stored bits
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut keys = HashMap::new();
+        while keys.len() < 20_000 {
+            let mut board = BitBoard::new();
+            for _ in 0..rng.gen_range(4..30) {
+                match board.random_move(&mut rng) {
+                    Some(column) if !board.is_winning(column) => board.play(column),
+                    _ => break,
+                };
+            }
+            keys.insert(board.key(), board.zobrist_key());
+        }
+        // Number of keys sharing their bucket with a previous key
+        fn collisions(keys: impl Iterator<Item = u64>, bucket: fn(u64) -> u64) -> usize {
+            let mut buckets: Vec<u64> = keys.map(bucket).collect();
+            let n_keys = buckets.len();
+            buckets.sort_unstable();
+            buckets.dedup();
+            n_keys - buckets.len()
+        }
+        let index: fn(u64) -> u64 = |key| key % ((1 << 23) + 9);
+        let stored: fn(u64) -> u64 = |key| key as u32 as u64;
+        let key_collisions = (collisions(keys.keys().copied(), index), collisions(keys.keys().copied(), stored));
+        let zobrist_collisions = (collisions(keys.values().copied(), index), collisions(keys.values().copied(), stored));
+        assert!(zobrist_collisions.1 < key_collisions.1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_position_census() {
+        assert_eq!(BitBoard::positions_at_depth(2).len(), WIDTH * WIDTH);
+
+        // No transpositions are possible after 2 moves, so every position except "44" is only shared with its mirror
+        let census = BitBoard::position_census(2);
+        assert_eq!(census.len(), 25);
+        assert_eq!(census.values().sum::<usize>(), 49);
+        assert_eq!(census.values().filter(|&&count| count == 2).count(), 24);
+        assert_eq!(census[&BitBoard::from_notation("44").canonical_key()], 1);
+        assert_eq!(census[&BitBoard::from_notation("12").canonical_key()], 2);
+    }
+
+    #[test]
+    fn test_creates_opponent_fork() {
+        // Player 1 threatens to complete the second row in B and F, and F is already playable
+        let board = BitBoard::from_notation("744556353");
+
+        // Playing B makes the second threat playable as well
+        assert!(board.creates_opponent_fork(Column::B));
+        Column::iter()
+            .filter(|&c| c != Column::B)
+            .for_each(|c| assert!(!board.creates_opponent_fork(c)));
+
+        // Blocking in F defuses the threat, so playing B afterwards concedes nothing
+        let mut board = board;
+        board.play(Column::F);
+        board.play(Column::G);
+        assert!(!board.creates_opponent_fork(Column::B));
+    }
+
+    #[test]
+    fn test_even_threat_advantage() {
+        assert_eq!(BitBoard::new().even_threat_advantage(), None);
+
+        // Player One threatens to complete the first row in G
+        let board = BitBoard::from_notation("435462");
+        assert_eq!(board.threats_by_parity(Player::One), (1, 0));
+        assert_eq!(board.threats_by_parity(Player::Two), (0, 0));
+        assert_eq!(board.even_threat_advantage(), Some(Player::One));
+
+        // Player Two threatens to complete the second row in A and E, neither is playable yet
+        let board = BitBoard::from_notation("443372422");
+        assert_eq!(board.threats_by_parity(Player::One), (0, 0));
+        assert_eq!(board.threats_by_parity(Player::Two), (0, 2));
+        assert_eq!(board.even_threat_advantage(), Some(Player::Two));
+
+        // Player One completes three on the third row, creating odd threats in A and E above Player Two's
+        let mut board = board;
+        board.play(Column::G);
+        board.play(Column::C);
+        assert_eq!(board.threats_by_parity(Player::One), (2, 0));
+        assert_eq!(board.even_threat_advantage(), None);
+    }
+
+    #[test]
+    fn test_threats() {
+        assert_eq!(BitBoard::new().threats(Player::One), 0);
+
+        // Player One has a double threat on the first row in C and G, and Player Two threatens C and G on
+        // the second row above them
+        let board = BitBoard::from_notation("445566");
+        assert_eq!(board.threats(Player::One), 2);
+        assert_eq!(board.threats(Player::Two), 2);
+        assert!(board.is_winning(Column::C) && board.is_winning(Column::G));
+
+        // A4 completes both a column and a row of Player One, and is counted once along with E4
+        let (e, x, o) = (Cell::Empty, Cell::P1, Cell::P2);
+        let cells = [
+            [x, o, o, x, e, e, e],
+            [x, x, o, o, e, e, e],
+            [x, o, o, o, e, e, e],
+            [e, x, x, x, e, e, e],
+            [e; WIDTH],
+            [e; WIDTH],
+        ];
+        let board = BitBoard::from_grid(cells, Player::Two).unwrap();
+        assert_eq!(board.threats(Player::One), 2);
+    }
+
+    #[test]
+    fn test_has_double_threat() {
+        assert!(!BitBoard::new().has_double_threat());
+        // An open three on the first row, but Player Two's threats above it can't be played yet
+        assert!(BitBoard::from_notation("445566").has_double_threat());
+        assert!(!BitBoard::from_notation("4455661").has_double_threat());
+
+        // A "7 trap": the row C3-E3 and the diagonal C1-E3 win on F3 and on F4 right above it.
+        // The other end of the row, B3, can't be played yet
+        let (e, x, o) = (Cell::Empty, Cell::P1, Cell::P2);
+        let mut cells = [
+            [e, o, x, o, o, x, e],
+            [e, e, o, x, o, o, e],
+            [e, e, x, x, x, e, e],
+            [e; WIDTH],
+            [e; WIDTH],
+            [e; WIDTH],
+        ];
+        let board = BitBoard::from_grid(cells, Player::One).unwrap();
+        assert_eq!(board.threats(Player::One), 3);
+        assert!(board.is_winning(Column::F) && !board.is_winning(Column::B));
+        assert!(board.has_double_threat());
+
+        // Once F3 is blocked, the threat on F4 is alone
+        cells[2][5] = o;
+        cells[0][0] = x;
+        let board = BitBoard::from_grid(cells, Player::One).unwrap();
+        assert!(board.is_winning(Column::F));
+        assert!(!board.has_double_threat());
+    }
+
+    #[test]
+    fn test_threat_differential() {
+        assert_eq!(BitBoard::new().threat_differential(), 0);
+
+        // The current player threatens to complete the first row in G
+        let mut board = BitBoard::from_notation("435462");
+        assert_eq!(board.threat_differential(), 1);
+
+        // Same threat, but now it's the opponent's turn
+        board.play(Column::A);
+        assert_eq!(board.threat_differential(), -1);
+
+        // The current player threatens to complete the second row in A and E
+        let mut board = BitBoard::from_notation("443372422");
+        assert_eq!(board.threat_differential(), 2);
+
+        // The opponent answers with two threats of their own on the third row
+        board.play(Column::G);
+        board.play(Column::C);
+        assert_eq!(board.threat_differential(), 0);
+    }
+
+    #[test]
+    fn test_theoretical_draw() {
+        let board = BitBoard::new();
+        assert_eq!(board.dead_cells(), 0);
+        assert!(!board.is_theoretical_draw());
+
+        // Live threat for the current player
+        assert!(!BitBoard::from_notation("435462").is_theoretical_draw());
+
+        // The only empty cell in E can't complete a line for anyone
+        let board = BitBoard::from_notation("71255763773133525731261364622167124446454");
+        assert_eq!(board.dead_cells(), 1 << (4 * (HEIGHT + 1) + 5));
+        assert!(board.is_theoretical_draw());
+
+        // Two empty cells in C, both dead
+        let board = BitBoard::from_notation("3576127617575661522124647446257235344113");
+        assert_eq!(board.dead_cells().count_ones(), 2);
+        assert!(board.is_theoretical_draw());
+
+        // A can still complete a vertical line for the opponent
+        let board = BitBoard::from_notation("22647455554314246733661634615122372377");
+        assert!(!board.is_theoretical_draw());
+
+        // The two threats of the player to move are upper cells of pairs of empty cells in A and E,
+        // which the opponent gets by playing on top of every move. The opponent has no line left
+        let board = BitBoard::from_notation("32644422163243436743562726367175175571");
+        assert_eq!(board.threats_by_parity(Player::One), (0, 2));
+        assert_ne!(board.dead_cells(), !board.mask & BitBoard::BOARD_MASK);
+        assert!(board.is_theoretical_draw());
+        // The columns of the empty board pair up too, but the lower cells hold lines
+        assert!(!BitBoard::new().is_theoretical_draw());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let board = BitBoard::from_notation("4453621");
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, format!(r#"{{"pos":{},"mask":{},"n_moves":7}}"#, board.pos, board.mask));
+        let decoded: BitBoard = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.key(), board.key());
+        assert_eq!(decoded.zobrist_key(), board.zobrist_key());
+        assert_eq!(decoded.number_of_moves(), 7);
+        assert!(decoded.history().is_empty());
+
+        // Player Two moving first is only written when it's the case
+        let swap = |cell| match cell {
+            Cell::P1 => Cell::P2,
+            Cell::P2 => Cell::P1,
+            Cell::Empty => Cell::Empty,
+        };
+        let cells = core::array::from_fn(|row| core::array::from_fn(|column| swap(board.cell(row, column))));
+        let two_first = BitBoard::from_grid(cells, Player::One).unwrap();
+        let json = serde_json::to_string(&two_first).unwrap();
+        assert!(json.ends_with(r#""n_moves":7,"two_first":true}"#), "{json}");
+        let decoded: BitBoard = serde_json::from_str(&json).unwrap();
+        assert_eq!((decoded, decoded.first_player()), (two_first, Player::Two));
+
+        let column: Column = serde_json::from_str(&serde_json::to_string(&Column::D).unwrap()).unwrap();
+        assert_eq!(column, Column::D);
+
+        // A stone of the current player on an empty cell, a stone floating above an empty cell,
+        // a stone off the 7x6 board, and a number of moves that doesn't match the stones
+        for json in [
+            r#"{"pos":2,"mask":1,"n_moves":1}"#,
+            r#"{"pos":0,"mask":2,"n_moves":1}"#,
+            r#"{"pos":0,"mask":64,"n_moves":1}"#,
+            r#"{"pos":0,"mask":1,"n_moves":2}"#,
+            r#"{"pos":1,"mask":1,"n_moves":1}"#,
+        ] {
+            assert!(serde_json::from_str::<BitBoard>(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let empty = BitBoard::new().fingerprint();
+        assert_eq!(empty, "AAAAAAAAAAA");
+
+        for board in BitBoard::positions_at_depth(4) {
+            let fingerprint = board.fingerprint();
+            assert_eq!(fingerprint.len(), 11);
+            let decoded = BitBoard::from_fingerprint(&fingerprint).unwrap();
+            assert_eq!(decoded.key(), board.key());
+            assert_eq!(decoded.number_of_moves(), board.number_of_moves());
+            assert_eq!(decoded.to_string(), board.to_string());
+        }
+
+        let board = BitBoard::from_notation("2252576253462244111563365343671351441");
+        let decoded = BitBoard::from_fingerprint(&board.fingerprint()).unwrap();
+        assert_eq!(decoded.key(), board.key());
+        assert_eq!(decoded.to_string(), board.to_string());
+
+        // Different move orders reaching the same position share a fingerprint
+        assert_eq!(BitBoard::from_notation("4352").fingerprint(), BitBoard::from_notation("5342").fingerprint());
+
+        assert_eq!(BitBoard::from_fingerprint("AAAA").err(), Some(FingerprintError::InvalidLength));
+        assert_eq!(BitBoard::from_fingerprint("AAAAAAAAAA*").err(), Some(FingerprintError::InvalidCharacter));
+        // Nonzero padding bits
+        assert_eq!(BitBoard::from_fingerprint("AAAAAAAAAAB").err(), Some(FingerprintError::InvalidPosition));
+        // More than HEIGHT stones in a column
+        assert_eq!(BitBoard::from_fingerprint("AAAAAAAAB_8").err(), Some(FingerprintError::InvalidPosition));
+        // The current player has too many stones
+        assert_eq!(BitBoard::from_fingerprint("AAAAAAAAAAI").err(), Some(FingerprintError::InvalidPosition));
+    }
+
+    #[test]
+    fn test_from_grid() {
+        for notation in ["", "4453", "2252576253462244111563365343671351441"] {
+            let board = BitBoard::from_notation(notation);
+            let cells = core::array::from_fn(|row| core::array::from_fn(|column| board.cell(row, column)));
+            let grid = BitBoard::from_grid(cells, board.current_player()).unwrap();
+            assert_eq!(grid, board, "{notation}");
+            assert_eq!(grid.number_of_moves(), board.number_of_moves());
+            assert_eq!(grid.zobrist_key(), board.zobrist_key());
+            assert!(grid.history().is_empty());
+        }
+
+        // Player One has won in the first column, but not with their last move
+        let mut cells = [[Cell::Empty; WIDTH]; HEIGHT];
+        for row in cells.iter_mut().take(4) {
+            row[0] = Cell::P1;
+            row[3] = Cell::P2;
+        }
+        let board = BitBoard::from_grid(cells, Player::One).unwrap();
+        assert_eq!(board.number_of_moves(), 8);
+        assert_eq!(board.cell(3, 3), Cell::P2);
+        assert_eq!(board.first_player(), Player::One);
+        // With as many pieces, player Two to move started the game
+        assert_eq!(BitBoard::from_grid(cells, Player::Two).unwrap().first_player(), Player::Two);
+
+        cells[5][6] = Cell::P1;
+        assert_eq!(BitBoard::from_grid(cells, Player::Two).err(), Some(InvalidGrid::FloatingPiece { row: 5, column: 6 }));
+        cells[5][6] = Cell::Empty;
+        cells[0][6] = Cell::P2;
+        assert_eq!(
+            BitBoard::from_grid(cells, Player::Two).err(),
+            Some(InvalidGrid::PieceCount { one: 4, two: 5, to_move: Player::Two })
+        );
+        assert_eq!(BitBoard::from_grid(cells, Player::One).unwrap().first_player(), Player::Two);
+        cells[1][6] = Cell::P2;
+        assert_eq!(
+            BitBoard::from_grid(cells, Player::One).err(),
+            Some(InvalidGrid::PieceCount { one: 4, two: 6, to_move: Player::One })
+        );
+
+        let mut small = [[Cell::Empty; 4]; 4];
+        small[0][1] = Cell::P1;
+        assert_eq!(SizedBitBoard::<4, 4>::from_grid(small, Player::Two).unwrap().landing_rows()[1], Some(1));
+    }
+
+    #[test]
+    fn test_player_two_first() {
+        // The position of a game started by player Two, which is the one of a notation with the colors swapped
+        let board = BitBoard::from_notation("17516442226766");
+        let swap = |cell| match cell {
+            Cell::P1 => Cell::P2,
+            Cell::P2 => Cell::P1,
+            Cell::Empty => Cell::Empty,
+        };
+        let cells = core::array::from_fn(|row| core::array::from_fn(|column| swap(board.cell(row, column))));
+        let mut swapped = BitBoard::from_grid(cells, Player::Two).unwrap();
+        assert_eq!(swapped.first_player(), Player::Two);
+        assert_eq!(swapped.key(), board.key());
+        assert_ne!(swapped, board);
+        assert_eq!(swapped.to_string(), board.to_string().replace('X', "x").replace('O', "X").replace('x', "O"));
+        assert!(swapped.pretty().ends_with("Move 15, O to play\n"));
+
+        let mut solver = crate::solver::Solver::new();
+        let score = solver.solve(&board).score;
+        solver.clear();
+        assert_eq!(solver.solve(&swapped).score, score);
+
+        // The players keep alternating from player Two
+        let zobrist = swapped.zobrist_key();
+        swapped.play(Column::A);
+        assert_eq!(swapped.current_player(), Player::One);
+        assert_eq!(swapped.cell(2, 0), Cell::P2);
+        swapped.unplay(Column::A);
+        assert_eq!(swapped.zobrist_key(), zobrist);
+        assert_eq!(swapped.null_move().current_player(), Player::One);
+        // The same stones with the other player to move are another position
+        assert_ne!(swapped.null_move().zobrist_key(), zobrist);
+        let grid = core::array::from_fn(|row| core::array::from_fn(|column| board.cell(row, column)));
+        let two_to_move = BitBoard::from_grid(grid, Player::Two).unwrap();
+        assert_eq!(two_to_move.stones(Player::One), board.stones(Player::One));
+        assert_ne!(two_to_move, board);
+        assert_ne!(two_to_move.zobrist_key(), board.zobrist_key());
+
+        // After an odd number of moves, the last one was player Two's
+        let mut cells = [[Cell::Empty; WIDTH]; HEIGHT];
+        for row in cells.iter_mut().take(4) {
+            row[0] = Cell::P2;
+            row[1] = Cell::P1;
+        }
+        cells[3][1] = Cell::Empty;
+        let won = BitBoard::from_grid(cells, Player::One).unwrap();
+        assert_eq!(won.status(), GameStatus::Won(Player::Two));
+    }
+
+    // Random legal positions for property tests, each reached by random moves from the empty board and stopped
+    // after a random number of moves. The game is never over: the generator stops instead of playing a win
+    #[cfg(feature = "std")]
+    fn random_positions<const W: usize, const H: usize, const N: usize>(
+        seed: u64,
+        count: usize,
+    ) -> impl Iterator<Item = SizedBitBoard<W, H, N>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count).map(move |_| {
+            let mut board = SizedBitBoard::new();
+            let length = rng.gen_range(0..W * H) as u32;
+            while board.number_of_moves() < length {
+                let Some(column) = board.random_move(&mut rng) else { break };
+                let player = board.current_player();
+                let mut next = board;
+                next.play(column);
+                if has_line(&next, player, None) {
+                    break;
+                }
+                board = next;
+            }
+            board
+        })
+    }
+
+    // Brute-force check that the player has N stones in a row, looking from every cell in every direction.
+    // `extra` is an empty cell to count as the player's, even if it isn't playable
+    #[cfg(feature = "std")]
+    fn has_line<const W: usize, const H: usize, const N: usize>(
+        board: &SizedBitBoard<W, H, N>,
+        player: Player,
+        extra: Option<(usize, usize)>,
+    ) -> bool {
+        let stone = if player == Player::One { Cell::P1 } else { Cell::P2 };
+        let owned = |row: isize, column: isize| {
+            (0..H as isize).contains(&row)
+                && (0..W as isize).contains(&column)
+                && (extra == Some((row as usize, column as usize)) || board.cell(row as usize, column as usize) == stone)
+        };
+        (0..H as isize).any(|row| {
+            (0..W as isize).any(|column| {
+                [(0, 1), (1, 0), (1, 1), (1, -1)]
+                    .iter()
+                    .any(|&(dr, dc)| (0..N as isize).all(|i| owned(row + i * dr, column + i * dc)))
+            })
+        })
+    }
+
+    // Checks the wins the bitboard computes against the brute-force checker, on random positions
+    #[cfg(feature = "std")]
+    fn check_winning_invariants<const W: usize, const H: usize, const N: usize>(seed: u64) {
+        for board in random_positions::<W, H, N>(seed, 200) {
+            let player = board.current_player();
+            let opponent = if player == Player::One { Player::Two } else { Player::One };
+            assert!(!has_line(&board, opponent, None), "{board:?}");
+
+            // is_winning(c) <=> playing c makes four in a row
+            for column in Column::iter() {
+                let wins = board.is_playable(column) && {
+                    let mut next = board;
+                    next.play(column);
+                    has_line(&next, player, None)
+                };
+                assert_eq!(board.is_winning(column), wins, "{} {column:?}", board.to_notation());
+            }
+
+            // The winning cells are the empty cells where a stone would make four in a row, playable or not
+            for (row, column) in (0..H).flat_map(|row| (0..W).map(move |column| (row, column))) {
+                let bit = 1 << (column * (H + 1) + row);
+                let empty = board.cell(row, column) == Cell::Empty;
+                let notation = board.to_notation();
+                let wins = empty && has_line(&board, player, Some((row, column)));
+                assert_eq!(board.winning_position() & bit != 0, wins, "{notation} ({row}, {column})");
+                let wins = empty && has_line(&board, opponent, Some((row, column)));
+                assert_eq!(board.opponent_winning_position() & bit != 0, wins, "{notation} ({row}, {column})");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_winning_invariants() {
+        check_winning_invariants::<WIDTH, HEIGHT, 4>(0);
+        check_winning_invariants::<WIDTH, HEIGHT, 5>(1);
+        check_winning_invariants::<5, 4, 3>(2);
+        check_winning_invariants::<4, 4, 4>(3);
+    }
+}
@@ -0,0 +1,282 @@
+//! Board of the Pop Out variant, where instead of dropping a piece a player may pop one of their own pieces
+//! out of the bottom of a column, shifting the pieces above it down.
+//!
+//! [PopOutBoard] doesn't implement [Board], so it can't be passed to the [crate::solver::Solver]: the scores of
+//! the solver count the empty cells left, and a game of Pop Out can last forever. It is searched with both kinds
+//! of moves by the [crate::solver::PopOutSolver] instead, to a number of moves. [PopOutBoard::board()] returns
+//! the pieces as a board of drops, which can be solved as if no more pieces could be popped.
+
+use super::*;
+
+/// A move of the Pop Out variant
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PopOutMove {
+    /// Drops a piece in the column, as [PopOutBoard::play()]
+    Drop(Column),
+    /// Removes the current player's piece at the bottom of the column, as [PopOutBoard::pop()]
+    Pop(Column),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PopOutBoard {
+    board: BitBoard,
+    moves: Vec<PopOutMove>, // every move played since the board was created, drops and pops
+}
+
+impl PopOutBoard {
+    pub fn new() -> Self {
+        PopOutBoard::default()
+    }
+
+    /// Creates a position from a sequence of drops, one column number or letter per move.
+    /// Panics if the sequence is invalid, see [BitBoard::try_from_notation()].
+    pub fn from_notation(notation: &str) -> Self {
+        let board = BitBoard::from_notation(notation);
+        let moves = board.history().iter().map(|&column| PopOutMove::Drop(column)).collect();
+        PopOutBoard { board, moves }
+    }
+
+    /// Returns the pieces on the board and the player to move, as a board of drops.
+    /// Its number of moves is the number of pieces, and its history only holds the drops since the last pop.
+    pub fn board(&self) -> &BitBoard {
+        &self.board
+    }
+
+    /// Returns every move played since the board was created, in order
+    pub fn move_list(&self) -> &[PopOutMove] {
+        &self.moves
+    }
+
+    /// Returns the number of moves played since the board was created, counting pops as well as drops
+    pub fn number_of_moves(&self) -> u32 {
+        self.moves.len() as u32
+    }
+
+    pub fn current_player(&self) -> Player {
+        self.board.current_player()
+    }
+
+    /// Checks if a piece can be dropped in the column, i.e. if there is still space in it
+    pub fn is_playable(&self, column: Column) -> bool {
+        self.board.is_playable(column)
+    }
+
+    /// Returns whether no piece can be dropped, which doesn't end the game as long as the player to move can pop
+    pub fn is_full(&self) -> bool {
+        Column::iter().all(|column| !self.is_playable(column))
+    }
+
+    /// Drops a piece of the current player in the column, which must be playable.
+    /// Returns the number of played moves.
+    pub fn play(&mut self, column: Column) -> u32 {
+        self.moves.push(PopOutMove::Drop(column));
+        self.board.play(column);
+        self.number_of_moves()
+    }
+
+    /// Reverses the last move, which must have been a drop in the given column.
+    /// Returns the number of played moves.
+    pub fn unplay(&mut self, column: Column) -> u32 {
+        debug_assert!(self.moves.last() == Some(&PopOutMove::Drop(column)), "Called unplay on another move than the last one");
+        self.moves.pop();
+        self.board.unplay(column);
+        self.number_of_moves()
+    }
+
+    /// Checks if the current player can pop the piece at the bottom of the column, i.e. if it is theirs
+    pub fn can_pop(&self, column: Column) -> bool {
+        self.board.can_pop(column)
+    }
+
+    /// Removes the current player's piece at the bottom of the column, shifting the pieces above it down.
+    /// Returns the number of played moves. The move must be possible, see [PopOutBoard::can_pop()].
+    /// Unlike a drop, a pop may complete a four-in-a-row for either player: see [PopOutBoard::status()].
+    pub fn pop(&mut self, column: Column) -> u32 {
+        self.moves.push(PopOutMove::Pop(column));
+        self.board.pop(column);
+        self.number_of_moves()
+    }
+
+    /// Reverses the last move, which must have been a pop in the given column.
+    /// Returns the number of played moves.
+    pub fn unpop(&mut self, column: Column) -> u32 {
+        debug_assert!(self.moves.last() == Some(&PopOutMove::Pop(column)), "Called unpop on another move than the last one");
+        self.moves.pop();
+        self.board.unpop(column);
+        self.number_of_moves()
+    }
+
+    /// Plays a drop or a pop of the current player, which must be possible. Returns the number of played moves.
+    pub fn play_move(&mut self, pop_out_move: PopOutMove) -> u32 {
+        match pop_out_move {
+            PopOutMove::Drop(column) => self.play(column),
+            PopOutMove::Pop(column) => self.pop(column),
+        }
+    }
+
+    /// Reverses the last move, which must have been the given one. Returns the number of played moves.
+    pub fn unplay_move(&mut self, pop_out_move: PopOutMove) -> u32 {
+        match pop_out_move {
+            PopOutMove::Drop(column) => self.unplay(column),
+            PopOutMove::Pop(column) => self.unpop(column),
+        }
+    }
+
+    /// Returns every drop and pop the current player can make, drops first
+    pub fn possible_moves(&self) -> Vec<PopOutMove> {
+        let drops = Column::iter().filter(|&c| self.is_playable(c)).map(PopOutMove::Drop);
+        let pops = Column::iter().filter(|&c| self.can_pop(c)).map(PopOutMove::Pop);
+        drops.chain(pops).collect()
+    }
+
+    /// Returns the possible moves after which the current player hasn't lost and the opponent can't win
+    /// with their next move, drops first. A pop loses right away when it only completes a four-in-a-row
+    /// of the opponent. The list is empty when every move loses, or when there are no possible moves.
+    pub fn possible_nonlosing_moves(&self) -> Vec<PopOutMove> {
+        let mut position = self.clone();
+        let mut moves = self.possible_moves();
+        moves.retain(|&pop_out_move| {
+            position.play_move(pop_out_move);
+            let nonlosing = match position.winner() {
+                Some(winner) => winner == self.current_player(),
+                None => position.possible_moves().into_iter().all(|reply| {
+                    position.play_move(reply);
+                    let winning = position.winner().is_some_and(|winner| winner != self.current_player());
+                    position.unplay_move(reply);
+                    !winning
+                }),
+            };
+            position.unplay_move(pop_out_move);
+            nonlosing
+        });
+        moves
+    }
+
+    /// Returns the player with a four-in-a-row, if any. A pop can complete a four-in-a-row for both players
+    /// at once, in which case the player who popped wins.
+    pub fn winner(&self) -> Option<Player> {
+        let current = self.current_player();
+        let opponent = if current == Player::One { Player::Two } else { Player::One };
+        match self.board.alignments() {
+            (_, true) => Some(opponent),
+            (true, false) => Some(current),
+            (false, false) => None,
+        }
+    }
+
+    /// Returns the state of the game, see [PopOutBoard::winner()]. A full board isn't a draw as long as
+    /// the player to move can pop. Draws by repetition aren't detected
+    pub fn status(&self) -> GameStatus {
+        match self.winner() {
+            Some(winner) => GameStatus::Won(winner),
+            None if self.is_full() && Column::iter().all(|column| !self.can_pop(column)) => GameStatus::Drawn,
+            None => GameStatus::InProgress,
+        }
+    }
+
+    // Keeps the allocation of the moves
+    pub fn reset(&mut self) {
+        self.board.reset();
+        self.moves.clear();
+    }
+}
+
+impl Display for PopOutBoard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.board.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_pop() {
+        let mut board = PopOutBoard::from_notation("4453");
+        assert!(board.can_pop(Column::D));
+        assert!(board.can_pop(Column::E));
+        assert!(!board.can_pop(Column::C)); // opponent's piece
+        assert!(!board.can_pop(Column::A)); // empty column
+
+        // Popping the bottom of column D leaves the second player's piece there
+        let key = board.board().key();
+        assert_eq!(board.pop(Column::D), 5);
+        assert_eq!(board.to_string(), "-------\n".repeat(HEIGHT - 1) + "--OOX--\n");
+        assert!(board.can_pop(Column::D) && !board.can_pop(Column::E));
+        assert_eq!(board.move_list().last(), Some(&PopOutMove::Pop(Column::D)));
+        assert_eq!(board.current_player(), Player::Two);
+        // The board of drops has the pieces left, with the same player to move
+        assert_eq!(board.board().number_of_moves(), 3);
+        assert_eq!(board.board().current_player(), Player::Two);
+
+        board.play(Column::A);
+        board.unplay(Column::A);
+        assert_eq!(board.unpop(Column::D), 4);
+        assert_eq!(board.board().key(), key);
+        assert_eq!(board.board().number_of_moves(), 4);
+        assert_eq!(board.move_list().len(), 4);
+
+        board.pop(Column::D);
+        board.reset();
+        assert_eq!(board.board().key(), PopOutBoard::new().board().key());
+        assert!(board.move_list().is_empty() && board.board().history().is_empty());
+    }
+
+    #[test]
+    fn test_possible_moves() {
+        let board = PopOutBoard::from_notation("12");
+        assert_eq!(board.possible_moves().len(), WIDTH + 1);
+        assert!(board.possible_moves().contains(&PopOutMove::Pop(Column::A)));
+
+        // A full board can still be played on by popping
+        let mut board = PopOutBoard::from_notation("675424577317223371452513267643443266156151");
+        assert!(board.is_full());
+        assert!(!board.possible_moves().is_empty());
+        assert!(board.possible_moves().iter().all(|m| matches!(m, PopOutMove::Pop(_))));
+        assert_eq!(board.status(), GameStatus::InProgress);
+
+        // after which it isn't full anymore
+        let PopOutMove::Pop(column) = board.possible_moves()[0] else { unreachable!() };
+        board.pop(column);
+        assert!(!board.is_full() && !board.board().is_full());
+        assert_eq!(board.number_of_moves(), 43);
+        assert_eq!(board.board().number_of_moves(), 41);
+    }
+
+    #[test]
+    fn test_possible_nonlosing_moves() {
+        // Both ends of the bottom row win
+        let board = PopOutBoard::from_notation("445566");
+        let moves = board.possible_nonlosing_moves();
+        assert!(moves.contains(&PopOutMove::Drop(Column::C)) && moves.contains(&PopOutMove::Drop(Column::G)));
+        assert!(moves.contains(&PopOutMove::Pop(Column::D)));
+
+        // only one of them can be blocked
+        assert!(PopOutBoard::from_notation("44556").possible_nonlosing_moves().is_empty());
+        // and every pop of the full board lets the opponent win
+        let board = PopOutBoard::from_notation("675424577317223371452513267643443266156151");
+        assert!(!board.possible_moves().is_empty() && board.possible_nonlosing_moves().is_empty());
+
+        // The popped piece of column A completes a row for both players, and the player who popped wins
+        let board = PopOutBoard::from_notation("1223344117");
+        assert!(board.possible_nonlosing_moves().contains(&PopOutMove::Pop(Column::A)));
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(PopOutBoard::new().status(), GameStatus::InProgress);
+        assert_eq!(PopOutBoard::from_notation("4455667").status(), GameStatus::Won(Player::One));
+
+        // Popping the bottom piece of column A completes a row for each player, and the player who popped wins
+        let mut board = PopOutBoard::from_notation("1223344117");
+        assert_eq!(board.status(), GameStatus::InProgress);
+        board.pop(Column::A);
+        assert_eq!(board.board.alignments(), (true, true));
+        assert_eq!(board.status(), GameStatus::Won(Player::One));
+        assert_eq!(board.winner(), Some(Player::One));
+        board.unplay_move(PopOutMove::Pop(Column::A));
+        assert_eq!(board.winner(), None);
+    }
+}
@@ -1,10 +1,27 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod benchmark;
 pub mod board;
+mod opening_book;
+#[cfg(feature = "python")]
+mod python;
 pub mod solver;
 mod transposition_table;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub mod prelude {
     //! The prelude of the connect4_solver crate, containing the most commonly used types and functions.
+    #[cfg(feature = "std")]
+    pub use crate::benchmark::load_benchmark_file;
+    pub use crate::benchmark::{parse_benchmark_line, ParseError};
     pub use crate::board::*;
+    pub use crate::opening_book::OpeningBook;
     pub use crate::solver::*;
-    pub use crate::transposition_table::TranspositionTable;
+    pub use crate::transposition_table::{
+        ConcurrentTranspositionTable, DepthPreferredTable, GenericTranspositionTable, TableStats, TableValue,
+        TranspositionTable,
+    };
 }
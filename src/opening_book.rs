@@ -0,0 +1,151 @@
+use crate::board::{BitBoard, Board, Column, HEIGHT, WIDTH};
+use crate::solver::Solver;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+use strum::IntoEnumIterator;
+
+/// An opening book maps the canonical keys of early positions to their exact scores, so that they don't
+/// need to be searched again. Books are expensive to generate, since the earliest positions are the slowest
+/// to solve, and meant to be generated once and then saved and loaded.
+/// See [Solver::set_opening_book()] to use one.
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    scores: BTreeMap<u64, i8>,
+}
+
+impl OpeningBook {
+    #[cfg(feature = "std")]
+    const FILE_MAGIC: &'static [u8; 4] = b"C4OB";
+
+    /// Creates a book of every position reachable from the empty board in up to `plies` moves
+    pub fn generate(plies: u32) -> Self {
+        Self::generate_from(&BitBoard::new(), plies)
+    }
+
+    /// Creates a book of every position reachable from `root` in up to `plies` moves.
+    /// Games that are over aren't stored, and only one of a position and its mirror image is solved.
+    pub fn generate_from(root: &BitBoard, plies: u32) -> Self {
+        let mut solver = Solver::new();
+        let mut book = Self::default();
+        let mut level = BTreeMap::from([(root.canonical_key(), *root)]);
+        for depth in 0..=plies {
+            let mut next_level = BTreeMap::new();
+            for position in level.values() {
+                book.scores.insert(position.canonical_key(), solver.solve(position).score as i8);
+                if depth == plies {
+                    continue;
+                }
+
+                for column in Column::iter().filter(|&column| position.is_playable(column)) {
+                    if !position.is_winning(column) {
+                        let mut next = *position;
+                        next.play(column);
+                        next_level.insert(next.canonical_key(), next);
+                    }
+                }
+            }
+            level = next_level;
+        }
+        book
+    }
+
+    /// Returns the number of positions in the book, counting a position and its mirror image once
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Returns the score of the position for the current player, if it is in the book.
+    /// Positions on boards of another size or win length are never found.
+    pub fn lookup<B: Board>(&self, position: &B) -> Option<i32> {
+        if B::WIDTH != WIDTH || B::HEIGHT != HEIGHT || B::WIN_LENGTH != 4 || !position.has_key() {
+            return None;
+        }
+        self.scores.get(&position.canonical_key()).map(|&score| score as i32)
+    }
+
+    /// Writes the positions of the book, preceded by their number, so that it can be read back with
+    /// [OpeningBook::load()]. The writer should be buffered, e.g. with a BufWriter.
+    #[cfg(feature = "std")]
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(Self::FILE_MAGIC)?;
+        w.write_all(&(self.scores.len() as u64).to_le_bytes())?;
+        for (key, score) in &self.scores {
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&score.to_le_bytes())?;
+        }
+        w.flush()
+    }
+
+    /// Reads a book written by [OpeningBook::save()].
+    /// Returns an error of kind InvalidData if the header isn't valid, and UnexpectedEof if the positions are truncated.
+    #[cfg(feature = "std")]
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != Self::FILE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an opening book file"));
+        }
+        let mut len = [0; 8];
+        r.read_exact(&mut len)?;
+
+        let mut book = Self::default();
+        for _ in 0..u64::from_le_bytes(len) {
+            let mut entry = [0; 9];
+            r.read_exact(&mut entry)?;
+            let key = u64::from_le_bytes(entry[..8].try_into().unwrap());
+            book.scores.insert(key, entry[8] as i8);
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::SizedBitBoard;
+
+    #[test]
+    fn test_generate() {
+        let root = BitBoard::from_notation("2252576253462244111563365343671351");
+        let book = OpeningBook::generate_from(&root, 2);
+        let mut solver = Solver::new();
+        for notation in [
+            "2252576253462244111563365343671351",
+            "22525762534622441115633653436713514",
+            "225257625346224411156336534367135144",
+        ] {
+            let position = BitBoard::from_notation(notation);
+            assert_eq!(book.lookup(&position), Some(solver.solve(&position).score), "{notation}");
+        }
+
+        let mirror = BitBoard::from_notation("6636312635426644777325523545217537");
+        assert_eq!(book.lookup(&mirror), book.lookup(&root));
+
+        // Positions further from the root, or whose game is over, aren't in the book
+        assert_eq!(book.lookup(&BitBoard::from_notation("2252576253462244111563365343671351441")), None);
+        assert_eq!(book.lookup(&BitBoard::new()), None);
+        assert_eq!(book.lookup(&SizedBitBoard::<6, 6>::new()), None);
+        let root = SizedBitBoard::<WIDTH, HEIGHT, 5>::from_notation("2252576253462244111563365343671351");
+        assert_eq!(book.lookup(&root), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_save_load() {
+        let book = OpeningBook::generate_from(&BitBoard::from_notation("7422341735647741166133573473242566"), 1);
+        let mut file = Vec::new();
+        book.save(&mut file).unwrap();
+        let loaded = OpeningBook::load(file.as_slice()).unwrap();
+        assert_eq!(loaded.scores, book.scores);
+
+        let error = OpeningBook::load(&file[..file.len() - 1]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        let error = OpeningBook::load(&file[1..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
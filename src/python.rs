@@ -0,0 +1,80 @@
+//! Python bindings of the solver, built with the python feature into a module named connect4_solver,
+//! e.g. with `maturin develop`.
+//!
+//! Positions are passed as notation strings, see [BitBoard::try_from_notation()], and columns are
+//! indices from 0 to 6. Invalid arguments raise a ValueError. The searches release the GIL.
+
+use crate::board::{BitBoard, Board, Column};
+use crate::solver::Solver;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::{LazyLock, Mutex};
+
+// Shared by all the calls, so that each doesn't allocate a table and positions reached by several are only
+// searched once
+static SOLVER: LazyLock<Mutex<Solver>> = LazyLock::new(|| Mutex::new(Solver::new()));
+
+fn parse(notation: &str) -> PyResult<BitBoard> {
+    BitBoard::try_from_notation(notation).map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
+/// Returns the score of the position for the current player
+#[pyfunction]
+fn solve(py: Python<'_>, notation: &str) -> PyResult<i32> {
+    let position = parse(notation)?;
+    Ok(py.allow_threads(|| SOLVER.lock().unwrap().solve(&position).score))
+}
+
+/// Returns the index of the best column for the current player, or None if the board is full
+#[pyfunction]
+fn best_move(py: Python<'_>, notation: &str) -> PyResult<Option<u8>> {
+    let position = parse(notation)?;
+    Ok(py.allow_threads(|| SOLVER.lock().unwrap().best_move(&position).map(|(column, _)| column as u8)))
+}
+
+/// A position, created from its notation
+#[pyclass(name = "Board")]
+struct PyBoard {
+    board: BitBoard,
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    #[pyo3(signature = (notation = ""))]
+    fn new(notation: &str) -> PyResult<Self> {
+        Ok(PyBoard { board: parse(notation)? })
+    }
+
+    /// Plays in the column, which must be playable, and returns the number of played moves
+    fn play(&mut self, column: usize) -> PyResult<u32> {
+        let column = self.playable_column(column)?;
+        Ok(self.board.play(column))
+    }
+
+    /// Checks if playing in the column, which must be playable, wins the game
+    fn is_winning(&self, column: usize) -> PyResult<bool> {
+        let column = self.playable_column(column)?;
+        Ok(self.board.is_winning(column))
+    }
+
+    fn __str__(&self) -> String {
+        self.board.to_string()
+    }
+}
+
+impl PyBoard {
+    fn playable_column(&self, column: usize) -> PyResult<Column> {
+        Column::from_repr(column)
+            .filter(|&column| self.board.is_playable(column))
+            .ok_or_else(|| PyValueError::new_err(format!("column {column} isn't playable")))
+    }
+}
+
+#[pymodule]
+fn connect4_solver(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(solve, module)?)?;
+    module.add_function(wrap_pyfunction!(best_move, module)?)?;
+    module.add_class::<PyBoard>()?;
+    Ok(())
+}
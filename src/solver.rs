@@ -1,27 +1,37 @@
 //! This module contains functions and structs to solve a Connect 4 position.
 use crate::board::*;
-use crate::transposition_table::TranspositionTable;
+use crate::opening_book::OpeningBook;
+use crate::transposition_table::{TableStats, TranspositionTable};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
 use heapless::binary_heap::{BinaryHeap, Max};
-use strum::EnumCount;
+use rand::Rng;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::time::Instant;
+use strum::{EnumCount, IntoEnumIterator};
 
-// Generate move order based on constant WIDTH instead of hardcoding it
-const COLUMN_ORDER: [Column; WIDTH] = generate_move_order();
-const fn unwrap_col(c: Option<Column>) -> Column {
-    match c {
-        Some(c) => c,
-        None => panic!("Invalid column"),
-    }
-}
-const fn generate_move_order() -> [Column; WIDTH] {
-    const MID: i32 = (Column::COUNT / 2) as i32;
+mod pop_out_solver;
+pub use pop_out_solver::{PopOutResult, PopOutSolver};
+
+const COLUMN_ORDER: [Column; WIDTH] = Column::center_out();
+// Number of low bits of a move priority that hold its rank in the move order
+const MOVE_ORDER_BITS: u32 = usize::BITS - (WIDTH - 1).leading_zeros();
+// Orders the first `width` columns from the center out, followed by the columns of wider boards
+const fn generate_move_order(width: usize) -> [Column; WIDTH] {
+    let mid = ((width - 1) / 2) as i32;
     let mut order = [Column::A; WIDTH];
     let mut index: i32 = 0;
     loop {
-        order[index as usize] = unwrap_col(Column::from_repr(
-            (MID - ((1 - 2 * (index % 2)) * (index + 1) / 2)) as usize,
-        ));
+        order[index as usize] = Column::all()[if index < width as i32 {
+            (mid - ((1 - 2 * (index % 2)) * (index + 1) / 2)) as usize
+        } else {
+            index as usize
+        }];
         index += 1;
-        if index >= WIDTH as i32 {
+        if index >= Column::COUNT as i32 {
             break;
         }
     }
@@ -29,194 +39,2643 @@ const fn generate_move_order() -> [Column; WIDTH] {
     order
 }
 
+// Orders the first `width` columns by their distance to the center, then by index, followed by the columns of
+// wider boards. Unlike the move order, the left one of two columns as close to the center comes first
+const fn generate_preference_order(width: usize) -> [Column; WIDTH] {
+    let mut order = [Column::A; WIDTH];
+    let mut index = 0;
+    let mut distance = 0; // twice the distance to the center, so that it is an integer for even widths
+    while distance < 2 * width {
+        let mut column = 0;
+        while column < width {
+            if (2 * column).abs_diff(width - 1) == distance {
+                order[index] = Column::all()[column];
+                index += 1;
+            }
+            column += 1;
+        }
+        distance += 1;
+    }
+    while index < WIDTH {
+        order[index] = Column::all()[index];
+        index += 1;
+    }
+
+    order
+}
+
 /// The result of a solve operation, containing the score of the position for the current player
 /// and the number of searched nodes.
+///
+/// ```
+/// use connect4_solver::prelude::*;
+///
+/// let result = Solver::new().solve(&BitBoard::from_notation("2252576253462244111563365343671351441"));
+/// assert_eq!(result.score, -1);
+/// assert!(result.complete && !result.heuristic);
+/// assert_eq!((result.min, result.max), (-1, -1));
+/// println!(
+///     "{} nodes in {:?}, {} table hits of which {} mirrored",
+///     result.nodes_searched, result.elapsed, result.table_hits, result.mirror_hits
+/// );
+/// ```
+#[must_use]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SolveResult {
     pub score: i32,
     pub nodes_searched: usize,
+    /// Time taken by the search. Without std there is no clock, and it is always zero
+    pub elapsed: Duration,
+    /// Number of transposition table lookups that found an entry, see [TableStats]
+    pub table_hits: u64,
+    /// Number of transposition table hits on an entry stored for the mirror image of the position,
+    /// which would have been misses without canonical keys
+    pub mirror_hits: usize,
+    /// Whether the score is exact. An incomplete search returns an estimate of the score
+    pub complete: bool,
+    /// Bounds of the window known to contain the score, both equal to the score when the search is complete
+    pub min: i32,
+    pub max: i32,
+    /// Whether the score is a heuristic evaluation rather than the score of the game, see [Solver::solve_to_depth()]
+    pub heuristic: bool,
+}
+
+impl SolveResult {
+    /// Creates the result of a complete search, with the given score and number of nodes. The other counts
+    /// are zero
+    pub fn new(score: i32, nodes_searched: usize) -> Self {
+        Self {
+            score,
+            nodes_searched,
+            elapsed: Duration::ZERO,
+            table_hits: 0,
+            mirror_hits: 0,
+            complete: true,
+            min: score,
+            max: score,
+            heuristic: false,
+        }
+    }
+
+    /// Returns the number of nodes searched per second, or 0 if no time was measured, e.g. without std
+    pub fn nodes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            0.0
+        } else {
+            self.nodes_searched as f64 / seconds
+        }
+    }
+
+    /// Returns the effective branching factor of the search, `nodes^(1/plies)`: the number of moves a tree
+    /// searching every move to `plies_searched` moves deep would need at each node to have the same size.
+    /// Pruning lowers it below the number of playable columns, and the lower it is the better the move ordering.
+    /// For a solve to the end of the game, `plies_searched` is the number of moves left. Returns 0 for 0 plies
+    #[cfg(feature = "std")]
+    pub fn effective_branching_factor(&self, plies_searched: u32) -> f64 {
+        if plies_searched == 0 {
+            0.0
+        } else {
+            (self.nodes_searched as f64).powf(1.0 / plies_searched as f64)
+        }
+    }
+}
+
+// Flag set on transposition table entries stored for a position whose key isn't its canonical key.
+// Stored scores never reach this bit.
+const MIRRORED_ENTRY: u8 = 0x80;
+
+/// The result of a game for one of the players
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The exact result of a position for the current player, created by [Solver::evaluate()].
+/// Moves are counted for the player who wins: `Win { in_moves: 1 }` wins on the next move,
+/// and `Loss { in_moves: 1 }` loses on the opponent's next move.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Evaluation {
+    Win { in_moves: u32 },
+    Loss { in_moves: u32 },
+    Draw,
+}
+
+impl Evaluation {
+    /// Decodes a score of the position for the current player, as returned by [Solver::solve()] with the
+    /// default draw value. The absolute value of the score is 1 plus the number of pieces the winner has left
+    /// after their winning move, the score being positive if the current player wins.
+    pub fn from_score<B: Board>(score: i32, position: &B) -> Self {
+        match score.signum() {
+            0 => Evaluation::Draw,
+            sign => {
+                // Number of moves of the game when the winner plays their winning piece, which has the parity
+                // of the current player's moves if they win, and of the opponent's otherwise
+                let cells = (B::WIDTH * B::HEIGHT) as u32;
+                let n_moves = position.number_of_moves();
+                let winner_parity = if sign > 0 { n_moves + 1 } else { n_moves };
+                let last_move = cells + 2 - 2 * score.unsigned_abs() - (cells + winner_parity) % 2;
+                let plies = last_move - n_moves;
+                if sign > 0 {
+                    Evaluation::Win { in_moves: plies.div_ceil(2) }
+                } else {
+                    Evaluation::Loss { in_moves: plies / 2 }
+                }
+            }
+        }
+    }
+}
+
+/// Converts a score, given from the point of view of `current_player` (the player to move in the solved
+/// position), into the outcome for `query_player`.
+pub fn outcome_for(score: i32, current_player: Player, query_player: Player) -> Outcome {
+    let score = if current_player == query_player { score } else { -score };
+    match score.signum() {
+        1 => Outcome::Win,
+        0 => Outcome::Draw,
+        _ => Outcome::Loss,
+    }
+}
+
+/// Returns the score of winning with the next move after `n_moves` moves: one plus the number of pieces the
+/// winner has left once they have played it. Winning sooner scores more, and losing to that move scores the
+/// opposite. So with a score s > 0, the player to move wins with their `(W * H + 1) / 2 + 1 - s`th piece,
+/// W * H being the number of cells, and with s < 0 their opponent wins with their `(W * H + 1) / 2 + 1 + s`th.
+#[inline]
+pub fn score<B: Board>(n_moves: u32) -> i32 {
+    ((B::WIDTH * B::HEIGHT + 1) as i32 - n_moves as i32) / 2
+}
+
+/// The result of a search limited to a number of moves, created by [Solver::solve_with_horizon()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HorizonResult {
+    /// The game is decided within the horizon, with the same score as [Solver::solve()]
+    Conclusive(i32),
+    /// Optimal play doesn't decide the game within the horizon
+    Inconclusive,
+}
+
+// Counters of the solver when a search started, see Solver::search_start()
+struct SearchStart {
+    mirror_hits: usize,
+    table_hits: u64,
+    #[cfg(feature = "std")]
+    time: Instant,
 }
 
+// Number of nodes between two calls of the progress callback
+const PROGRESS_INTERVAL: usize = 1 << 20;
+
+// State shared by the recursive calls of a search
 #[derive(Default)]
+struct Search<'a> {
+    nodes_searched: usize,
+    on_progress: Option<&'a mut dyn FnMut(usize)>,
+    cancel: Option<&'a AtomicBool>,
+    trace: Option<&'a mut Trace<'a>>,
+}
+
+impl Search<'_> {
+    // Checks the cancellation flag every few nodes, like the deadline
+    fn cancelled(&self) -> bool {
+        self.nodes_searched.is_multiple_of(1024) && self.cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+// A node of a traced search, see Solver::solve_traced(). Only std builds write them
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+struct TraceNode {
+    id: usize, // nodes are numbered in the order they're entered
+    parent: Option<usize>,
+    depth: u32,
+    column: Option<Column>, // the move played to reach the node
+    key: u64,
+    alpha: i32,
+    beta: i32,
+    score: i32,
+    cutoff: Option<Column>, // the move whose score reached beta
+}
+
+// Records the nodes of a search up to a depth, passing each to `on_node` once it has been searched
+struct Trace<'a> {
+    root_moves: u32,
+    max_depth: u32,
+    path: Vec<(usize, Option<Column>)>, // id and cutoff move of the traced nodes being searched, from the root
+    n_nodes: usize,
+    on_node: &'a mut dyn FnMut(&TraceNode),
+}
+
+impl Trace<'_> {
+    fn is_traced(&self, position: &impl Board) -> bool {
+        position.number_of_moves() - self.root_moves <= self.max_depth
+    }
+}
+
+/// An iterator over the score windows narrowed by the search, created by [Solver::solve_iter()].
+/// Each item is the `(min, max)` window after one iteration, the last one has `min == max`.
+pub struct SolveIter<'a, B: Board> {
+    solver: &'a mut Solver,
+    position: B,
+    min: i32,
+    max: i32,
+    search: Search<'a>,
+    done: bool,
+}
+
+impl<B: Board> SolveIter<'_, B> {
+    /// Returns the number of nodes searched so far
+    pub fn nodes_searched(&self) -> usize {
+        self.search.nodes_searched
+    }
+}
+
+impl<B: Board> Iterator for SolveIter<'_, B> {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // A position won in one move starts converged, and is yielded once
+        if self.min < self.max {
+            let (min, max) = self.solver.narrow_window(&mut self.position, &mut self.search, self.min, self.max);
+            self.min = min;
+            self.max = max;
+        }
+
+        self.done = self.min >= self.max || self.solver.aborted;
+        Some((self.min, self.max))
+    }
+}
+
+/// An iterator over the positions of a game where both players play [Solver::best_move()], created by
+/// [Solver::self_play_iter()]. Each item is a position and its score for the player to move, and the
+/// position that ends the game isn't yielded.
+pub struct SelfPlayIter<'a> {
+    solver: &'a mut Solver,
+    position: Option<BitBoard>, // the next position to yield, None once the game is over
+}
+
+impl Iterator for SelfPlayIter<'_> {
+    type Item = (BitBoard, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.position.take()?;
+        let (column, score) = self.solver.best_move(&position)?;
+        if !position.is_winning(column) {
+            let mut next_position = position;
+            next_position.play(column);
+            self.position = (!next_position.is_full()).then_some(next_position);
+        }
+        Some((position, score))
+    }
+}
+
 pub struct Solver {
     table: TranspositionTable,
+    canonical_keys: bool,
+    draw_value: i32,
+    draw_parity: u32, // parity of the number of moves in positions where a draw is worth draw_value
+    board_size: (usize, usize, usize), // width, height and win length of the boards of the positions in the tables
+    mirror_hits: usize,
+    move_limit: Option<u32>, // number of moves after which a horizon search stops, treating the game as undecided
+    evaluate_horizon: bool,  // whether positions at the move limit are evaluated by their threats instead of as draws
+    // Table swapped with the regular one during horizon searches, whose scores only hold for their move limit:
+    // the horizon table between them, the regular table during them. It has an eighth of the entries
+    spare_table: Option<TranspositionTable>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+    nodes_left: Option<usize>,
+    aborted: bool, // set when a search limit is reached during a search, whose results must then be discarded
+    history_heuristic: bool,
+    history: [u32; WIDTH * (HEIGHT + 1)], // cutoffs caused by a move in each cell, weighted by depth, by bit of the cell
+    move_order: Option<[Column; WIDTH]>,  // order of the moves with the same priority, center-out if None
+    book: Option<OpeningBook>,
+    best_move_ordering: bool,
+    best_moves: TranspositionTable, // 1 + the column that last caused a cutoff in each position, by key
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The heuristics ordering the moves that [Board::score_move()] scores the same, see
+/// [Solver::set_history_heuristic()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum MoveOrdering {
+    /// Only [Board::score_move()] and the column order
+    Static,
+    #[default]
+    History,
+}
+
+/// Configures a [Solver] in one expression, with the same defaults as [Solver::new()]
+///
+/// ```
+/// use connect4_solver::prelude::*;
+///
+/// let mut solver = SolverBuilder::default().table_bits(25).build();
+/// assert_eq!(solver.solve(&BitBoard::from_notation("7422341735647741166133573473242566")).score, 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SolverBuilder {
+    table_bits: Option<u32>,
+    book: Option<OpeningBook>,
+    move_ordering: MoveOrdering,
+}
+
+impl SolverBuilder {
+    /// Sets the size of the transposition table to about 2^bits entries, see [TranspositionTable::with_bits()]
+    pub fn table_bits(mut self, bits: u32) -> Self {
+        self.table_bits = Some(bits);
+        self
+    }
+
+    /// See [Solver::set_opening_book()]
+    pub fn use_opening_book(mut self, book: OpeningBook) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    pub fn move_ordering(mut self, move_ordering: MoveOrdering) -> Self {
+        self.move_ordering = move_ordering;
+        self
+    }
+
+    pub fn build(self) -> Solver {
+        let mut solver = match self.table_bits {
+            Some(bits) => Solver::with_table_bits(bits),
+            None => Solver::new(),
+        };
+        solver.set_opening_book(self.book);
+        solver.set_history_heuristic(self.move_ordering == MoveOrdering::History);
+        solver
+    }
 }
 
 // Public API
 impl Solver {
     pub fn new() -> Self {
+        Self::with_table(TranspositionTable::default())
+    }
+
+    /// Creates a solver whose transposition table has about 2^bits entries, see [TranspositionTable::with_bits()].
+    /// The table of the moves that caused cutoffs, which orders the moves, has an eighth of that, and so has
+    /// the table of the horizon searches, allocated by the first one.
+    pub fn with_table_bits(bits: u32) -> Self {
+        Self::with_table(TranspositionTable::with_bits(bits))
+    }
+
+    /// Creates a solver using the given transposition table, e.g. one loaded with [TranspositionTable::load()].
+    /// The table must have been filled with the same draw value and the same [Solver::set_canonical_keys()] setting.
+    pub fn with_table(table: TranspositionTable) -> Self {
+        // The best moves only order the moves, so they get an eighth of the entries of the table
+        let best_moves_bits = (table.size().ilog2() - 3).max(TranspositionTable::MIN_BITS);
         Self {
-            table: TranspositionTable::default(),
+            table,
+            canonical_keys: true,
+            draw_value: 0,
+            draw_parity: 0,
+            board_size: (WIDTH, HEIGHT, 4),
+            mirror_hits: 0,
+            move_limit: None,
+            evaluate_horizon: false,
+            spare_table: None,
+            #[cfg(feature = "std")]
+            deadline: None,
+            nodes_left: None,
+            aborted: false,
+            history_heuristic: true,
+            history: [0; WIDTH * (HEIGHT + 1)],
+            move_order: None,
+            book: None,
+            best_move_ordering: true,
+            best_moves: TranspositionTable::with_bits(best_moves_bits),
         }
     }
 
+    /// Returns the transposition table, e.g. to save it with [TranspositionTable::save()].
+    /// The moves that caused cutoffs are in another table, which only orders the moves and isn't included.
+    pub fn table(&self) -> &TranspositionTable {
+        &self.table
+    }
+
     pub fn clear(&mut self) {
         self.table.clear();
+        self.best_moves.clear();
+        self.history = [0; WIDTH * (HEIGHT + 1)];
+    }
+
+    /// Returns the transposition table lookup counts since the solver was created or last cleared
+    pub fn table_stats(&self) -> TableStats {
+        self.table.stats()
+    }
+
+    /// Selects whether the transposition table is indexed by [Board::canonical_key()] (the default)
+    /// or by [Board::key()]. Canonical keys let a position and its mirror image share an entry.
+    /// Since mirrored positions have the same score, the table doesn't need to be cleared when switching.
+    pub fn set_canonical_keys(&mut self, enabled: bool) {
+        self.canonical_keys = enabled;
+    }
+
+    /// Selects whether the moves are ordered by the cutoffs caused by moves in the same cell so far
+    /// (the "history heuristic"), among the moves with the same [Board::score_move()] score and best move,
+    /// and as far from the center, or from the start of the [Solver::set_move_order()] order.
+    /// Cutoffs weigh more the more moves are left, and moves searched without a cutoff lose as much.
+    /// On by default: the scores are the same either way, it only reduces the number of searched nodes.
+    pub fn set_history_heuristic(&mut self, enabled: bool) {
+        self.history_heuristic = enabled;
+    }
+
+    /// Selects whether the move that last caused a cutoff in a position is searched first among the moves
+    /// with the same [Board::score_move()] score, when the position is searched again,
+    /// e.g. by the next iteration of [Solver::solve()]. On by default, it only changes the number of searched nodes.
+    /// The moves are still stored when it is off, so it can be turned back on without clearing the solver.
+    pub fn set_best_moves(&mut self, enabled: bool) {
+        self.best_move_ordering = enabled;
+    }
+
+    /// Sets the order in which the moves with the same priority are searched, instead of the default order
+    /// from the center out. The priority of a move comes from [Board::score_move()] and the other heuristics,
+    /// so the order only breaks their ties. Columns the board doesn't have are skipped.
+    /// Like the other heuristics it only changes the number of searched nodes, not the scores.
+    ///
+    /// # Panics
+    /// If a column is missing from the order
+    pub fn set_move_order(&mut self, order: [Column; WIDTH]) {
+        assert!(Column::iter().all(|column| order.contains(&column)), "Move order {order:?} is missing a column");
+        self.move_order = Some(order);
+    }
+
+    /// Sets the opening book whose positions are solved without searching, or removes it with None.
+    /// The book is only used for exact scores, i.e. not with a draw value or a horizon.
+    pub fn set_opening_book(&mut self, book: Option<OpeningBook>) {
+        self.book = book;
+    }
+
+    /// Sets the score of a draw for the player to move in the position passed to the solver (0 by default).
+    /// With -1 a draw is as bad as losing with the last piece, so a player that needs a win avoids drawn lines.
+    /// With 1 a draw is as good as winning with the last piece.
+    /// With a nonzero value the returned scores are no longer the exact game-theoretic scores.
+    /// The value must be -1, 0 or 1: the search always takes an immediate win and never allows an immediate
+    /// loss, which would no longer be optimal if a draw could outweigh the slowest win or loss.
+    pub fn set_draw_value(&mut self, draw_value: i32) {
+        assert!((-1..=1).contains(&draw_value), "Draw value must be -1, 0 or 1");
+        if draw_value != self.draw_value {
+            self.draw_value = draw_value;
+            self.table.clear();
+        }
+    }
+
+    /// Returns the lowest and highest scores the position can have for the player to move, the window searched by
+    /// [Solver::solve()]: from losing to the opponent's next move, `-score::<B>(n + 1)`, to winning with the next
+    /// move, `score::<B>(n)`, where n is the number of moves played, see [score()]. A nonzero draw value widens it
+    /// to include the score of a draw.
+    pub fn score_bounds<B: Board>(&self, position: &B) -> (i32, i32) {
+        let n_moves = position.number_of_moves();
+        let min = -score::<B>(n_moves + 1);
+        let max = score::<B>(n_moves);
+        (min.min(-self.draw_value.abs()), max.max(self.draw_value.abs()))
     }
 
     pub fn solve(&mut self, position: &impl Board) -> SolveResult {
-        if position.can_win_in_one_move() {
-            return SolveResult {
-                score: score(position.number_of_moves()),
-                nodes_searched: 1,
-            };
+        self.set_draw_perspective(position);
+        self.solve_position(position)
+    }
+
+    /// Solves the positions in order, without clearing the transposition table between them, so that the
+    /// positions sharing subtrees, e.g. the children of a common position, reuse each other's entries.
+    /// Keeping the table is always safe, since its entries are bounds of the exact scores of their positions,
+    /// whichever search stored them; clearing it only frees its memory. The exception is a draw value other
+    /// than 0, with which the table is cleared whenever the player to move changes, see [Solver::set_draw_value()]:
+    /// positions with the same player to move should then be grouped together.
+    pub fn solve_batch<B: Board>(&mut self, positions: &[B]) -> Vec<SolveResult> {
+        positions.iter().map(|position| self.solve(position)).collect()
+    }
+
+    /// Solves the position by searching the moves of the current player on up to `threads` threads.
+    /// Each thread uses its own transposition table, so memory usage grows with the number of threads,
+    /// and this solver's table isn't filled by the search.
+    #[cfg(feature = "std")]
+    pub fn solve_parallel<B: Board + Send>(&mut self, position: &B, threads: usize) -> SolveResult {
+        if threads <= 1 || position.can_win_in_one_move() {
+            return self.solve(position);
+        }
+        let start = Instant::now();
+
+        let children: Vec<B> = COLUMN_ORDER
+            .into_iter()
+            .filter(|&column| position.is_playable(column))
+            .map(|column| {
+                let mut child = position.clone();
+                child.play(column);
+                child
+            })
+            .collect();
+        if children.is_empty() {
+            return self.solve(position);
+        }
+
+        // Deal the moves to the workers in move order, so the most promising ones are searched first
+        let n_workers = threads.min(children.len());
+        let mut work: Vec<Vec<B>> = (0..n_workers).map(|_| Vec::new()).collect();
+        for (i, child) in children.into_iter().enumerate() {
+            work[i % n_workers].push(child);
         }
 
-        let mut min = -(WIDTH as i32 * HEIGHT as i32 - position.number_of_moves() as i32) / 2;
-        let mut max = (WIDTH as i32 * HEIGHT as i32 + 1 - position.number_of_moves() as i32) / 2;
-        let mut nodes = 0;
+        self.set_draw_perspective(position);
+        let results: Vec<SolveResult> = std::thread::scope(|scope| {
+            let workers: Vec<_> = work
+                .into_iter()
+                .map(|children| {
+                    let mut solver = self.worker();
+                    scope.spawn(move || {
+                        children
+                            .iter()
+                            .map(|child| solver.solve_position(child))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        });
 
-        while min < max {
-            let mut mid = min + (max - min) / 2;
-            if mid <= 0 && min / 2 < mid {
-                mid = min / 2;
-            } else if mid >= 0 && max / 2 > mid {
-                mid = max / 2;
+        let score = results.iter().map(|result| -result.score).max().unwrap();
+        SolveResult {
+            elapsed: start.elapsed(),
+            table_hits: results.iter().map(|result| result.table_hits).sum(),
+            mirror_hits: results.iter().map(|result| result.mirror_hits).sum(),
+            ..SolveResult::new(score, 1 + results.iter().map(|result| result.nodes_searched).sum::<usize>())
+        }
+    }
+
+    /// Solves the position, calling `on_progress` with the number of nodes searched so far
+    /// every 2^20 nodes.
+    pub fn solve_with_progress(&mut self, position: &impl Board, mut on_progress: impl FnMut(usize)) -> SolveResult {
+        self.set_draw_perspective(position);
+        self.search_position(position, Some(&mut on_progress))
+    }
+
+    /// Solves the position, giving up once `budget` has elapsed. If the search is cut short, the result
+    /// isn't complete and its score is the middle of the window known to contain the score.
+    #[cfg(feature = "std")]
+    pub fn solve_within(&mut self, position: &impl Board, budget: Duration) -> SolveResult {
+        self.deadline = Some(Instant::now() + budget);
+        let result = self.solve(position);
+        self.deadline = None;
+        self.aborted = false;
+        result
+    }
+
+    /// Solves the position, giving up once `max_nodes` nodes have been searched. If the search is cut short,
+    /// the result isn't complete and holds the bounds found so far, see [Solver::solve_within()].
+    /// Starting from the same table contents, e.g. with a new or cleared solver, the same nodes are searched.
+    pub fn solve_with_node_limit(&mut self, position: &impl Board, max_nodes: usize) -> SolveResult {
+        self.nodes_left = Some(max_nodes);
+        let result = self.solve(position);
+        self.nodes_left = None;
+        self.aborted = false;
+        result
+    }
+
+    /// Solves the position unless `cancel` is set, e.g. by another thread, before the search is over.
+    /// The flag is read between the iterations of the search and every few nodes, and None is returned
+    /// as soon as it is found set. The solver can be used again after a cancelled search.
+    pub fn solve_cancellable(&mut self, position: &impl Board, cancel: &AtomicBool) -> Option<SolveResult> {
+        self.set_draw_perspective(position);
+        let start = self.search_start();
+        let mut windows = self.windows(position);
+        windows.search.cancel = Some(cancel);
+        while !cancel.load(Ordering::Relaxed) && windows.next().is_some() {}
+
+        let (min, max, nodes_searched) = (windows.min, windows.max, windows.search.nodes_searched);
+        let result = self.window_result(min, max, nodes_searched, &start);
+        self.aborted = false;
+        result.complete.then_some(result)
+    }
+
+    /// Solves the position step by step, yielding the window known to contain the score after each
+    /// iteration of the search. The final window holds the same score as [Solver::solve()].
+    pub fn solve_iter<B: Board>(&mut self, position: &B) -> SolveIter<'_, B> {
+        self.set_draw_perspective(position);
+        self.windows(position)
+    }
+
+    /// Solves the position like [Solver::solve()], writing the nodes searched up to `max_depth` moves from it
+    /// to `out`, one JSON object per line, to inspect the search tree. For example:
+    /// `{"id":5,"parent":4,"depth":1,"move":"5","key":27179366745605,"alpha":-12,"beta":-11,"score":-11,"cutoff":"7"}`
+    ///
+    /// Nodes are numbered in the order they're searched, and written once their search is over, so after
+    /// their children. Each iteration of the search starts a new tree, whose root has no parent nor move.
+    /// The key is the Zobrist key for boards without a key, the alpha and beta bounds are the window the node
+    /// was searched with, and the cutoff is the move whose score reached beta, if any.
+    /// The opening book isn't used, so that the position is searched, and a position won in one move isn't.
+    /// The search continues after a write error, which is then returned.
+    #[cfg(feature = "std")]
+    pub fn solve_traced(&mut self, position: &impl Board, max_depth: u32, out: &mut impl Write) -> io::Result<SolveResult> {
+        let column = |column: Option<Column>| column.map_or("null".to_string(), |column| format!("\"{}\"", char::from(column)));
+        let mut result = Ok(());
+        let mut on_node = |node: &TraceNode| {
+            if result.is_ok() {
+                result = writeln!(
+                    out,
+                    r#"{{"id":{},"parent":{},"depth":{},"move":{},"key":{},"alpha":{},"beta":{},"score":{},"cutoff":{}}}"#,
+                    node.id,
+                    node.parent.map_or("null".to_string(), |parent| parent.to_string()),
+                    node.depth,
+                    column(node.column),
+                    node.key,
+                    node.alpha,
+                    node.beta,
+                    node.score,
+                    column(node.cutoff),
+                );
             }
+        };
+        let mut trace = Trace {
+            root_moves: position.number_of_moves(),
+            max_depth,
+            path: Vec::new(),
+            n_nodes: 0,
+            on_node: &mut on_node,
+        };
+
+        self.set_draw_perspective(position);
+        let start = self.search_start();
+        let mut windows = self.windows(position);
+        windows.search.trace = Some(&mut trace);
+        for _ in windows.by_ref() {}
+        let (min, max, nodes_searched) = (windows.min, windows.max, windows.search.nodes_searched);
+        let solve_result = self.window_result(min, max, nodes_searched, &start);
+        result.and(out.flush()).map(|_| solve_result)
+    }
 
-            // Since the score is bounded by the number of moves, there's an implicit depth limit in the search that
-            // depends on beta.
-            let mut nodes_searched = 0;
-            let score = self.solve_impl(position, &mut nodes_searched, mid, mid + 1);
-            if score > mid {
-                min = score;
+    /// Solves the position with MTD(f): null window searches at the last score found, starting from
+    /// `first_guess`, each of which moves one bound of the window to that score. A close guess,
+    /// e.g. the score of the position before the last two moves, converges in fewer searches than
+    /// the bisection of [Solver::solve()], which remains the default.
+    pub fn solve_mtdf<B: Board>(&mut self, position: &B, first_guess: i32) -> SolveResult {
+        self.set_draw_perspective(position);
+        let start = self.search_start();
+        let mut windows = self.windows(position);
+        let SolveIter { solver, position, min, max, search, .. } = &mut windows;
+        let mut guess = first_guess.clamp(*min, *max);
+        while *min < *max {
+            let beta = if guess == *min { guess + 1 } else { guess };
+            guess = solver.solve_impl(position, search, beta - 1, beta);
+            if solver.aborted {
+                break;
+            }
+            if guess < beta {
+                *max = guess;
             } else {
-                max = score;
+                *min = guess;
             }
-            nodes += nodes_searched;
         }
 
-        SolveResult {
-            score: min,
-            nodes_searched: nodes,
-        }
+        let (min, max, nodes_searched) = (windows.min, windows.max, windows.search.nodes_searched);
+        self.window_result(min, max, nodes_searched, &start)
     }
-}
 
-// Private API
-impl Solver {
-    fn solve_impl(
-        &mut self,
-        position: &impl Board,
-        nodes_searched: &mut usize,
-        mut alpha: i32,
-        mut beta: i32,
-    ) -> i32 {
-        *nodes_searched += 1;
+    /// Searches the position with the window `[alpha, beta]`, e.g. a null window `[beta - 1, beta]` telling whether
+    /// the score is at least beta, to share the windows of one position between separate searches.
+    /// A score inside the window is exact, but a score on or outside one of its edges is only a bound:
+    /// the exact score is at most a score `<= alpha`, and at least a score `>= beta`.
+    /// The result's `min` and `max` hold the range the exact score is known to be in, and it's complete if they meet.
+    ///
+    /// # Panics
+    /// If the window is empty, i.e. `alpha >= beta`
+    pub fn solve_window<B: Board>(&mut self, position: &B, alpha: i32, beta: i32) -> SolveResult {
+        assert!(alpha < beta, "Empty window [{alpha}, {beta}]");
+        self.set_draw_perspective(position);
+        let start = self.search_start();
+        let mut windows = self.windows(position);
+        let SolveIter { solver, position, min, max, search, .. } = &mut windows;
+        if *min < *max {
+            let score = solver.solve_impl(position, search, alpha, beta);
+            if !solver.aborted {
+                if score <= alpha {
+                    *max = score.min(*max);
+                } else if score >= beta {
+                    *min = score.max(*min);
+                } else {
+                    (*min, *max) = (score, score);
+                }
+            }
+        }
 
-        let possible_moves = position.possible_nonlosing_moves();
+        let (min, max, nodes_searched) = (windows.min, windows.max, windows.search.nodes_searched);
+        self.window_result(min, max, nodes_searched, &start)
+    }
 
-        // Stop conditions
-        // 1 - No possible non-losing moves -> opponent wins next turn
-        if possible_moves == 0 {
-            return -((WIDTH * HEIGHT) as i32 - position.number_of_moves() as i32) / 2;
+    /// Solves the position assuming the game has to end within `horizon_plies` more moves.
+    /// If optimal play wins or loses within the horizon, or fills the board, the exact score is returned.
+    /// Otherwise the result is inconclusive: the position may be a draw or be decided later in the game.
+    /// The search uses a table of its own, so the entries of the regular searches are kept.
+    pub fn solve_with_horizon<B: Board>(&mut self, position: &B, horizon_plies: u32) -> HorizonResult {
+        let move_limit = position.number_of_moves() + horizon_plies;
+        if move_limit >= (B::WIDTH * B::HEIGHT) as u32 {
+            return HorizonResult::Conclusive(self.solve(position).score);
         }
-
-        // 2 - Draw. All moves have been made without a win (actually, prune a bit ealier since a win is no longer possible at this point)
-        if position.number_of_moves() >= (WIDTH as u32 * HEIGHT as u32) - 2 {
-            return 0;
+        if horizon_plies == 0 {
+            return HorizonResult::Inconclusive;
         }
 
-        // Lower bound since opponent cannot win next move (possible moves are not empty)
-        let mut min = -((WIDTH * HEIGHT - 2) as i32 - position.number_of_moves() as i32) / 2;
-        if alpha < min {
-            // update alpha and possibly prune
-            alpha = min;
-            if alpha >= beta {
-                return alpha;
-            }
+        self.start_horizon(position, move_limit, false);
+        let score = self.solve(position).score;
+        self.end_horizon();
+
+        // Positions cut at the horizon score 0, within the evaluation bound, while the games decided within
+        // the horizon score beyond it. There are no draws before the end of the game, which is solved above.
+        if score.abs() <= evaluation_bound::<B>(move_limit) {
+            HorizonResult::Inconclusive
+        } else {
+            HorizonResult::Conclusive(score)
         }
+    }
 
-        // Maximum achievable score since position.number_of_moves() moves have been made so far
-        // This maximum score changes every turn, so we need to account of it in beta before iterating
-        let mut max = ((WIDTH * HEIGHT - 1) as u32 - position.number_of_moves()) as i32 / 2;
-        if beta > max {
-            beta = max;
-            if alpha >= beta {
-                return beta;
-            }
+    /// Solves the position approximately, searching at most `max_plies` more moves. Games decided within the
+    /// depth get their exact score, while the positions reached at the depth are evaluated by the difference between
+    /// the [Board::threats()] of the player to move and of their opponent. Evaluations are bounded below the score
+    /// of any game decided within the depth, and the result is flagged as heuristic when its score is one.
+    /// Searching to the end of the game returns the same result as [Solver::solve()].
+    pub fn solve_to_depth<B: Board>(&mut self, position: &B, max_plies: u32) -> SolveResult {
+        // A position won in one move isn't searched
+        let move_limit = position.number_of_moves() + max_plies;
+        if move_limit >= (B::WIDTH * B::HEIGHT) as u32 || position.can_win_in_one_move() {
+            return self.solve(position);
         }
 
-        // Check transposition table
-        const MIN_SCORE: i32 = -((WIDTH * HEIGHT) as i32 / 2) + 3;
-        const MAX_SCORE: i32 = ((WIDTH * HEIGHT + 1) as i32 / 2) - 3;
-        let key = position.key();
-        if let Some(score) = self.table.get(key) {
-            if score > (MAX_SCORE - MIN_SCORE + 1) as u8 {
-                // score is a lower bound
-                min = score as i32 - MAX_SCORE + 2 * MIN_SCORE - 2;
-                if alpha < min {
-                    alpha = min;
-                    if alpha >= beta {
-                        return alpha;
-                    }
+        self.start_horizon(position, move_limit, true);
+        let mut result = self.solve(position);
+        self.end_horizon();
+
+        result.heuristic = result.score.abs() <= evaluation_bound::<B>(move_limit);
+        result
+    }
+
+    /// Returns the playable column with the best score for the current player, along with that score,
+    /// or None if the board is full. Ties are broken in favor of the column closest to the center,
+    /// then of the lower column: on the standard board, in the order D, C, E, B, F, A, G.
+    pub fn best_move(&mut self, position: &impl Board) -> Option<(Column, i32)> {
+        self.set_draw_perspective(position);
+        best_of(&self.move_scores(position))
+    }
+
+    /// Returns the move of [Solver::best_move()] with its evaluation, making explicit how it picks among moves
+    /// with the same outcome: scores count down with the moves played, so the highest score wins in the fewest
+    /// moves, and if every move loses, it loses in the most moves. Draws are preferred to losses only.
+    /// The draw value must be 0, see [Solver::evaluate()].
+    pub fn best_move_optimal_speed<B: Board>(&mut self, position: &B) -> Option<(Column, Evaluation)> {
+        assert_eq!(self.draw_value, 0, "Evaluations need the default draw value");
+        let (column, score) = self.best_move(position)?;
+        Some((column, Evaluation::from_score(score, position)))
+    }
+
+    /// Returns the line of optimal play from the position, alternating the moves of the current player and
+    /// of their opponent, until a winning move or a full board. Each move is the one [Solver::best_move()]
+    /// would choose, the center-most of the moves keeping the score of the position.
+    pub fn principal_variation<B: Board>(&mut self, position: &B) -> Vec<Column> {
+        self.set_draw_perspective(position);
+        let mut line = Vec::new();
+        let mut board = position.clone();
+        let mut score = self.solve_position(&board).score;
+        loop {
+            // The first column in order of preference whose score is the score of the position, from the mover's side
+            let mut best = None;
+            for column in const { generate_preference_order(B::WIDTH) } {
+                if !board.is_playable(column) {
+                    continue;
                 }
-            } else {
-                // score is an upper bound
-                max = score as i32 + MIN_SCORE - 1;
-                if beta > max {
-                    beta = max;
-                    if alpha >= beta {
-                        return beta;
-                    }
+                if board.is_winning(column) {
+                    best = Some((column, true));
+                    break;
+                }
+
+                let mut next_position = board.clone();
+                next_position.play(column);
+                if -self.solve_position(&next_position).score == score {
+                    best = Some((column, false));
+                    break;
                 }
             }
-        }
 
-        if beta > max {
-            // the lower bound of the position score is the best the opponent can do (new upper bound for us)
-            beta = max;
-            if alpha >= beta {
-                return alpha;
+            let Some((column, wins)) = best else {
+                return line; // board is full
+            };
+            line.push(column);
+            if wins {
+                return line;
             }
+            board.play(column);
+            score = -score;
         }
+    }
 
-        // Sort moves by priority, defaulting to priority in COLUMN_ORDER
-        let mut heap: BinaryHeap<_, Max, WIDTH> = BinaryHeap::new();
+    /// Plays the game from `start` with [Solver::best_move()] for both players, yielding each position before
+    /// the end of the game along with its exact score, e.g. to build a dataset of solved positions.
+    /// Nothing is yielded if `start` is already over.
+    pub fn self_play_iter(&mut self, start: BitBoard) -> SelfPlayIter<'_> {
+        SelfPlayIter {
+            solver: self,
+            position: (start.status() == GameStatus::InProgress).then_some(start),
+        }
+    }
+
+    /// Scores every column for the current player: the score after playing it, or None if the column is full.
+    pub fn analyze(&mut self, position: &impl Board) -> [Option<i32>; WIDTH] {
+        self.set_draw_perspective(position);
+        let mut scores = [None; WIDTH];
+        for (column, score) in self.move_scores(position) {
+            scores[column as usize] = Some(score);
+        }
+        scores
+    }
+
+    /// Solves the position only up to whether it's won, drawn or lost for the current player.
+    /// Searching with the narrowest window around a draw cuts the search off much earlier than [Solver::solve()].
+    /// The draw value must be 0: otherwise a draw has the score of the slowest win or loss.
+    pub fn solve_weak(&mut self, position: &impl Board) -> Outcome {
+        assert_eq!(self.draw_value, 0, "Weak solving needs the default draw value");
+        self.set_draw_perspective(position);
+        self.weak_solve(position)
+    }
+
+    /// Solves the position and decodes its score into the number of moves until the game is won or lost.
+    /// The draw value must be 0: otherwise a draw has the score of the slowest win or loss.
+    pub fn evaluate<B: Board>(&mut self, position: &B) -> Evaluation {
+        assert_eq!(self.draw_value, 0, "Evaluations need the default draw value");
+        Evaluation::from_score(self.solve(position).score, position)
+    }
+
+    /// Returns a column that is proven to win, or None if the position isn't won.
+    /// An immediate win is returned if there is one, otherwise the first winning column in the move order.
+    /// Moves are only evaluated as won or not won, which is faster than computing their exact scores.
+    /// The draw value must be 0, as for [Solver::solve_weak()]: otherwise a move that draws could count as a win.
+    pub fn find_any_win(&mut self, position: &impl Board) -> Option<Column> {
+        assert_eq!(self.draw_value, 0, "Finding a win needs the default draw value");
+        if let Some(column) = COLUMN_ORDER.into_iter().find(|&c| position.is_winning(c)) {
+            return Some(column);
+        }
+
+        self.set_draw_perspective(position);
+        COLUMN_ORDER.into_iter().find(|&column| {
+            if !position.is_playable(column) {
+                return false;
+            }
+
+            let mut next_position = position.clone();
+            next_position.play(column);
+            self.weak_solve(&next_position) == Outcome::Loss
+        })
+    }
+
+    /// Checks if the player to move is in zugzwang: every move leads to a worse score than they would get
+    /// if they could pass and let the opponent move on the same board.
+    /// Passing isn't allowed in Connect 4, the passed position is evaluated as the opponent to move.
+    /// The draw value must be 0: the passed position has the same number of moves, so the draw value
+    /// would apply to the opponent there.
+    pub fn is_zugzwang<const W: usize, const H: usize, const N: usize>(
+        &mut self,
+        position: &SizedBitBoard<W, H, N>,
+    ) -> bool {
+        assert_eq!(self.draw_value, 0, "Zugzwang needs the default draw value");
+        let score = self.solve(position).score;
+        let pass_score = -self.solve(&position.null_move()).score;
+        score < pass_score
+    }
+
+    /// Lists, for each move of the current player, the opponent's best reply and the resulting score
+    /// for the current player, as `(move, reply, score)` in move order.
+    /// Moves that end the game leave nothing to reply to and aren't listed.
+    pub fn reply_analysis(&mut self, position: &impl Board) -> Vec<(Column, Column, i32)> {
+        self.set_draw_perspective(position);
+        let mut analysis = Vec::new();
         for column in COLUMN_ORDER {
-            if possible_moves & BitBoard::column_mask(column) != 0 {
-                heap.push(position.score_move(column)).unwrap();
+            if !position.is_playable(column) || position.is_winning(column) {
+                continue;
             }
-        }
 
-        while let Some(ScoredMove { column, .. }) = heap.pop() {
-            let mut next_position = *position;
+            let mut next_position = position.clone();
             next_position.play(column);
-            let score = -self.solve_impl(&next_position, nodes_searched, -beta, -alpha);
-            if score >= beta {
-                // Save the lower bound of the position score
-                self.table
-                    .set(key, (score + MAX_SCORE - 2 * MIN_SCORE + 2) as u8);
-                // our possible score is better than the worst score the opponent can make us get
-                return score;
+            if let Some((reply, score)) = best_of(&self.move_scores(&next_position)) {
+                analysis.push((column, reply, -score));
             }
-            alpha = alpha.max(score);
         }
 
-        self.table.set(key, (alpha - MIN_SCORE + 1) as u8); // save the upper bound of the position score
-
-        alpha
+        analysis
     }
-}
 
-#[inline]
-fn score(n_moves: u32) -> i32 {
-    ((WIDTH * HEIGHT + 1) as i32 - n_moves as i32) / 2
-}
+    /// Estimates the expected result of playing from `position` against an opponent that plays uniformly
+    /// random moves, while the current player keeps playing the best move found by the solver.
+    /// Each of the `samples` games counts 1 for a win, 0.5 for a draw and 0 for a loss, and the average is returned.
+    pub fn expected_score_vs_random(&mut self, position: &impl Board, samples: usize, rng: &mut impl Rng) -> f32 {
+        let mut total = 0.0;
+        for _ in 0..samples {
+            let mut board = position.clone();
+            let mut our_turn = true;
+            loop {
+                let column = if our_turn {
+                    self.best_move(&board).map(|(column, _)| column)
+                } else {
+                    board.random_move(rng)
+                };
 
-#[cfg(test)]
-mod test {
-    use super::*;
+                let Some(column) = column else {
+                    total += 0.5; // board is full
+                    break;
+                };
 
-    #[test]
-    fn test_score() {
-        // Win on 4th stone of player 1 -> each player played 3 so far
-        assert_eq!(score(6), 18);
+                if board.is_winning(column) {
+                    if our_turn {
+                        total += 1.0;
+                    }
+                    break;
+                }
+
+                board.play(column);
+                our_turn = !our_turn;
+            }
+        }
+
+        total / samples as f32
+    }
+}
+
+// Private API
+impl Solver {
+    // Makes the draw value apply to the player to move in `position`. Table entries depend on it
+    // when the draw value isn't 0, so they're discarded when the player changes.
+    // They're also discarded for a position of another board size or win length, since boards of the same height
+    // have the same keys for different positions
+    fn set_draw_perspective<B: Board>(&mut self, position: &B) {
+        if self.board_size != (B::WIDTH, B::HEIGHT, B::WIN_LENGTH) {
+            self.board_size = (B::WIDTH, B::HEIGHT, B::WIN_LENGTH);
+            self.table.clear();
+            self.best_moves.clear();
+        }
+        let parity = position.number_of_moves() % 2;
+        if parity != self.draw_parity {
+            self.draw_parity = parity;
+            if self.draw_value != 0 {
+                self.table.clear();
+            }
+        }
+    }
+
+    // Score of a draw for the player to move in `position`
+    fn draw_score(&self, position: &impl Board) -> i32 {
+        if position.number_of_moves() % 2 == self.draw_parity {
+            self.draw_value
+        } else {
+            -self.draw_value
+        }
+    }
+
+    // Limits the next searches of the position to the move limit. Their scores aren't valid for other searches,
+    // so they use an empty table of their own, keeping the regular table instead of clearing it. The draw
+    // perspective is set first, so that it is the regular table that gets cleared if it changes
+    fn start_horizon<B: Board>(&mut self, position: &B, move_limit: u32, evaluate_horizon: bool) {
+        self.set_draw_perspective(position);
+        let bits = (self.table.size().ilog2() - 3).max(TranspositionTable::MIN_BITS);
+        let mut table = self.spare_table.take().unwrap_or_else(|| TranspositionTable::with_bits(bits));
+        table.clear();
+        self.spare_table = Some(core::mem::replace(&mut self.table, table));
+        self.move_limit = Some(move_limit);
+        self.evaluate_horizon = evaluate_horizon;
+    }
+
+    fn end_horizon(&mut self) {
+        let table = self.spare_table.take().expect("No horizon search was started");
+        self.spare_table = Some(core::mem::replace(&mut self.table, table));
+        self.move_limit = None;
+        self.evaluate_horizon = false;
+    }
+
+    // Returns a solver with an empty table of the same size and the same configuration, to search in another thread
+    #[cfg(feature = "std")]
+    fn worker(&self) -> Solver {
+        Solver {
+            canonical_keys: self.canonical_keys,
+            draw_value: self.draw_value,
+            draw_parity: self.draw_parity,
+            board_size: self.board_size,
+            history_heuristic: self.history_heuristic,
+            best_move_ordering: self.best_move_ordering,
+            move_order: self.move_order,
+            book: self.book.clone(),
+            // the size of the table is the smallest prime from 2^bits, below 2^(bits + 1)
+            ..Solver::with_table_bits(self.table.size().ilog2())
+        }
+    }
+
+    // Checks the deadline set by solve_within every few nodes, reading the clock on every node
+    // would slow the search down
+    #[cfg(feature = "std")]
+    fn past_deadline(&self, nodes_searched: usize) -> bool {
+        nodes_searched.is_multiple_of(1024) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // Without std there is no clock, so solve_within isn't available and no deadline is ever set
+    #[cfg(not(feature = "std"))]
+    fn past_deadline(&self, _nodes_searched: usize) -> bool {
+        false
+    }
+
+    // Solves the position without changing the draw perspective, looking it up in the opening book first
+    fn solve_position<B: Board>(&mut self, position: &B) -> SolveResult {
+        let exact = self.draw_value == 0 && self.move_limit.is_none();
+        if let Some(score) = self.book.as_ref().filter(|_| exact).and_then(|book| book.lookup(position)) {
+            return SolveResult::new(score, 0);
+        }
+        self.search_position(position, None)
+    }
+
+    // Solves the position without changing the draw perspective, reporting the progress to the callback
+    fn search_position<B: Board>(&mut self, position: &B, on_progress: Option<&mut dyn FnMut(usize)>) -> SolveResult {
+        let start = self.search_start();
+        let mut windows = self.windows(position);
+        if let Some(on_progress) = on_progress {
+            windows.search.on_progress = Some(on_progress);
+        }
+        for _ in windows.by_ref() {}
+        let (min, max, nodes_searched) = (windows.min, windows.max, windows.search.nodes_searched);
+        self.window_result(min, max, nodes_searched, &start)
+    }
+
+    // Snapshot of the counters a search result reports the increase of, to take once the table won't be cleared
+    // before the search, e.g. by set_draw_perspective
+    fn search_start(&self) -> SearchStart {
+        SearchStart {
+            mirror_hits: self.mirror_hits,
+            table_hits: self.table.stats().hits,
+            #[cfg(feature = "std")]
+            time: Instant::now(),
+        }
+    }
+
+    // Result of a search that narrowed the window to [min, max], which may have been cut short
+    fn window_result(&self, min: i32, max: i32, nodes_searched: usize, start: &SearchStart) -> SolveResult {
+        let complete = min >= max;
+        SolveResult {
+            score: if complete { min } else { min + (max - min) / 2 },
+            #[cfg(feature = "std")]
+            elapsed: start.time.elapsed(),
+            table_hits: self.table.stats().hits - start.table_hits,
+            mirror_hits: self.mirror_hits - start.mirror_hits,
+            complete,
+            min,
+            max,
+            ..SolveResult::new(0, nodes_searched)
+        }
+    }
+
+    // Iterates over the score windows without changing the draw perspective
+    fn windows<B: Board>(&mut self, position: &B) -> SolveIter<'_, B> {
+        if position.can_win_in_one_move() {
+            let score = score::<B>(position.number_of_moves());
+            return SolveIter {
+                solver: self,
+                position: position.clone(),
+                min: score,
+                max: score,
+                search: Search {
+                    nodes_searched: 1,
+                    ..Search::default()
+                },
+                done: false,
+            };
+        }
+
+        let (min, max) = self.score_bounds(position);
+        SolveIter {
+            solver: self,
+            position: position.clone(),
+            min,
+            max,
+            search: Search::default(),
+            done: false,
+        }
+    }
+
+    // Runs one null window search in the [min, max] window and returns the narrowed window
+    fn narrow_window(&mut self, position: &mut impl Board, search: &mut Search, mut min: i32, mut max: i32) -> (i32, i32) {
+        let mut mid = min + (max - min) / 2;
+        if mid <= 0 && min / 2 < mid {
+            mid = min / 2;
+        } else if mid >= 0 && max / 2 > mid {
+            mid = max / 2;
+        }
+
+        // Since the score is bounded by the number of moves, there's an implicit depth limit in the search that
+        // depends on beta.
+        let score = if search.trace.is_some() {
+            self.traced_impl(position, search, mid, mid + 1)
+        } else {
+            self.solve_impl(position, search, mid, mid + 1)
+        };
+        if self.aborted {
+            return (min, max);
+        }
+
+        if score > mid {
+            min = score;
+        } else {
+            max = score;
+        }
+
+        (min, max)
+    }
+
+    // Weakly solves the position without changing the draw perspective
+    fn weak_solve(&mut self, position: &impl Board) -> Outcome {
+        if position.can_win_in_one_move() {
+            return Outcome::Win;
+        }
+
+        let score = self.solve_impl(&mut position.clone(), &mut Search::default(), -1, 1);
+        match score.signum() {
+            1 => Outcome::Win,
+            0 => Outcome::Draw,
+            _ => Outcome::Loss,
+        }
+    }
+
+    // Scores every playable column for the current player, in order of preference, without changing the draw perspective
+    fn move_scores<B: Board>(&mut self, position: &B) -> Vec<(Column, i32)> {
+        let mut scores = Vec::new();
+        for column in const { generate_preference_order(B::WIDTH) } {
+            if !position.is_playable(column) {
+                continue;
+            }
+
+            let score = if position.is_winning(column) {
+                score::<B>(position.number_of_moves())
+            } else {
+                let mut next_position = position.clone();
+                next_position.play(column);
+                -self.solve_position(&next_position).score
+            };
+            scores.push((column, score));
+        }
+
+        scores
+    }
+
+    #[inline]
+    fn solve_impl<B: Board>(&mut self, position: &mut B, search: &mut Search, alpha: i32, beta: i32) -> i32 {
+        self.search_node::<B, false>(position, search, alpha, beta)
+    }
+
+    // Searches the position like solve_impl, recording it in the trace of the search unless it's deeper than
+    // the trace's limit. Only traced searches use it, so that the others don't check for a trace on every node
+    fn traced_impl<B: Board>(&mut self, position: &mut B, search: &mut Search, alpha: i32, beta: i32) -> i32 {
+        let trace = search.trace.as_mut().unwrap();
+        let traced = trace.is_traced(position);
+        if traced {
+            trace.path.push((trace.n_nodes, None));
+            trace.n_nodes += 1;
+        }
+
+        let score = self.search_node::<B, true>(position, search, alpha, beta);
+        if traced {
+            let trace = search.trace.as_mut().unwrap();
+            let (id, cutoff) = trace.path.pop().unwrap();
+            let depth = position.number_of_moves() - trace.root_moves;
+            let node = TraceNode {
+                id,
+                parent: trace.path.last().map(|&(parent, _)| parent),
+                depth,
+                column: position.history().last().copied().filter(|_| depth > 0),
+                key: if position.has_key() { position.key() } else { position.zobrist_key() },
+                alpha,
+                beta,
+                score,
+                cutoff,
+            };
+            (trace.on_node)(&node);
+        }
+        score
+    }
+
+    // Searches a child of a node, traced if the node is
+    #[inline(always)]
+    fn search_child<B: Board, const TRACE: bool>(
+        &mut self,
+        position: &mut B,
+        search: &mut Search,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if TRACE {
+            self.traced_impl(position, search, alpha, beta)
+        } else {
+            self.solve_impl(position, search, alpha, beta)
+        }
+    }
+
+    fn search_node<B: Board, const TRACE: bool>(
+        &mut self,
+        position: &mut B,
+        search: &mut Search,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        // Check the search limits
+        if self.nodes_left == Some(0) || self.past_deadline(search.nodes_searched) || search.cancelled() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        search.nodes_searched += 1;
+        if search.nodes_searched.is_multiple_of(PROGRESS_INTERVAL) {
+            if let Some(on_progress) = &mut search.on_progress {
+                on_progress(search.nodes_searched);
+            }
+        }
+        if let Some(nodes_left) = &mut self.nodes_left {
+            *nodes_left -= 1;
+        }
+
+        let possible_moves = position.possible_nonlosing_moves();
+
+        // Stop conditions
+        // 0 - Horizon reached. The current player can't win next move, so the only result left before the
+        // move limit is the opponent winning on their next move
+        if let Some(limit) = self.move_limit {
+            let opponent_win = position.number_of_moves() + 2;
+            if opponent_win > limit || (opponent_win == limit && possible_moves != 0) {
+                return if self.evaluate_horizon { evaluate_at_limit(position, limit) } else { 0 };
+            }
+        }
+
+        // 1 - No possible non-losing moves -> opponent wins next turn
+        if possible_moves == 0 {
+            return -((B::WIDTH * B::HEIGHT) as i32 - position.number_of_moves() as i32) / 2;
+        }
+
+        // 2 - Draw. All moves have been made without a win (actually, prune a bit ealier since a win is no longer possible at this point)
+        if position.number_of_moves() >= (B::WIDTH * B::HEIGHT) as u32 - 2 {
+            return self.draw_score(position);
+        }
+
+        // Lower bound since opponent cannot win next move (possible moves are not empty)
+        let mut min = -((B::WIDTH * B::HEIGHT - 2) as i32 - position.number_of_moves() as i32) / 2;
+        min = min.min(-self.draw_value.abs());
+        if alpha < min {
+            // update alpha and possibly prune
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        // Maximum achievable score since position.number_of_moves() moves have been made so far
+        // This maximum score changes every turn, so we need to account of it in beta before iterating
+        let mut max = ((B::WIDTH * B::HEIGHT - 1) as u32 - position.number_of_moves()) as i32 / 2;
+        max = max.max(self.draw_value.abs());
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        // Check transposition table
+        // Bounds of the scores on the standard board, which also hold the scores of smaller boards
+        const MIN_SCORE: i32 = -((WIDTH * HEIGHT) as i32 / 2) + 3;
+        const MAX_SCORE: i32 = ((WIDTH * HEIGHT + 1) as i32 / 2) - 3;
+        // Entries remember whether they were stored for the mirror image of the canonical position.
+        // Positions without a key aren't stored at all
+        let table_key = position.has_key().then(|| {
+            if self.canonical_keys {
+                let key = position.canonical_key();
+                let orientation = if key != position.key() { MIRRORED_ENTRY } else { 0 };
+                (key, orientation)
+            } else {
+                (position.key(), 0)
+            }
+        });
+        if let Some((entry, orientation)) =
+            table_key.and_then(|(key, orientation)| Some((self.table.get(key)?, orientation)))
+        {
+            if entry & MIRRORED_ENTRY != orientation {
+                self.mirror_hits += 1;
+            }
+
+            let score = entry & !MIRRORED_ENTRY;
+            if score > (MAX_SCORE - MIN_SCORE + 1) as u8 {
+                // score is a lower bound
+                min = score as i32 - MAX_SCORE + 2 * MIN_SCORE - 2;
+                if alpha < min {
+                    alpha = min;
+                    if alpha >= beta {
+                        return alpha;
+                    }
+                }
+            } else {
+                // score is an upper bound
+                max = score as i32 + MIN_SCORE - 1;
+                if beta > max {
+                    beta = max;
+                    if alpha >= beta {
+                        return beta;
+                    }
+                }
+            }
+        }
+
+        if beta > max {
+            // the lower bound of the position score is the best the opponent can do (new upper bound for us)
+            beta = max;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        // Sort moves by priority, defaulting to priority in the move order (of the board width if not set),
+        // which is kept in the lowest bits so that no two moves have the same priority.
+        // The move that caused a cutoff in this position in an earlier search, e.g. the previous iteration,
+        // comes first among the moves with the same priority.
+        // The history then only orders the moves as far from the start of the move order, e.g. the two columns
+        // at the same distance from the center: ordering all the moves by it searches more nodes
+        let ply = position.number_of_moves() as usize;
+        // Best moves are stored for the orientation of the key
+        let orient = |column: Column, orientation: u8| {
+            if orientation != 0 {
+                Column::from_repr(B::WIDTH - 1 - column as usize).unwrap()
+            } else {
+                column
+            }
+        };
+        let best_move = table_key.filter(|_| self.best_move_ordering).and_then(|(key, orientation)| {
+            let column = self.best_moves.get(key)? as usize - 1;
+            // another board size can have stored a column that doesn't exist on this one
+            (column < B::WIDTH).then(|| orient(Column::from_repr(column).unwrap(), orientation))
+        });
+        let mut heap: BinaryHeap<_, Max, WIDTH> = BinaryHeap::new();
+        let column_mask = |column: Column| ((1 << B::HEIGHT) - 1) << (column as usize * (B::HEIGHT + 1));
+        let move_order = self.move_order.unwrap_or(const { generate_move_order(B::WIDTH) });
+        for (rank, column) in move_order.into_iter().enumerate() {
+            if possible_moves & column_mask(column) != 0 {
+                let mut scored_move = position.score_move(column);
+                // The history weights are capped to 16 bits, below them are the rank in the move order
+                let history = if self.history_heuristic {
+                    self.history[(possible_moves & column_mask(column)).trailing_zeros() as usize].min(0xffff)
+                } else {
+                    0
+                };
+                let best = (best_move == Some(column)) as u32;
+                // Pairs of consecutive ranks, after the first one on boards of odd width
+                let distance = ((rank + B::WIDTH % 2) / 2) as u32;
+                let priority = (scored_move.score * 2 + best) * 4 + 3 - distance;
+                scored_move.score = (priority << 16 | history) << MOVE_ORDER_BITS | (WIDTH - 1 - rank) as u32;
+                heap.push(scored_move).unwrap();
+            }
+        }
+
+        // Principal variation search: the first move is searched with the full window, the others with a null
+        // window that only tells whether they beat alpha, and again with the full window if they do.
+        // The searches started by narrow_window already use null windows, where both are the same
+        let mut first_move = true;
+        while let Some(ScoredMove { column, .. }) = heap.pop() {
+            position.play(column);
+            let mut score;
+            if first_move || beta - alpha <= 1 {
+                score = -self.search_child::<B, TRACE>(position, search, -beta, -alpha);
+            } else {
+                score = -self.search_child::<B, TRACE>(position, search, -alpha - 1, -alpha);
+                if score > alpha && score < beta && !self.aborted {
+                    score = -self.search_child::<B, TRACE>(position, search, -beta, -alpha);
+                }
+            }
+            first_move = false;
+            position.unplay(column);
+            if self.aborted {
+                // the score is meaningless, and mustn't be stored in the table
+                return 0;
+            }
+            if score >= beta {
+                if self.history_heuristic {
+                    let cell = (possible_moves & column_mask(column)).trailing_zeros() as usize;
+                    // Cutoffs far from the end of the game prune more nodes, so they weigh more
+                    let remaining_moves = (B::WIDTH * B::HEIGHT - ply) as u32;
+                    self.history[cell] = self.history[cell].saturating_add(remaining_moves * remaining_moves);
+                }
+                if TRACE {
+                    let trace = search.trace.as_mut().unwrap();
+                    if trace.is_traced(position) {
+                        trace.path.last_mut().unwrap().1 = Some(column);
+                    }
+                }
+                // Save the lower bound of the position score, and the move to try first next time
+                if let Some((key, orientation)) = table_key {
+                    self.table
+                        .set(key, (score + MAX_SCORE - 2 * MIN_SCORE + 2) as u8 | orientation);
+                    self.best_moves.set(key, orient(column, orientation) as u8 + 1);
+                }
+                // our possible score is better than the worst score the opponent can make us get
+                return score;
+            }
+            if self.history_heuristic {
+                // and moves that don't cause a cutoff lose as much
+                let cell = (possible_moves & column_mask(column)).trailing_zeros() as usize;
+                let remaining_moves = (B::WIDTH * B::HEIGHT - ply) as u32;
+                self.history[cell] = self.history[cell].saturating_sub(remaining_moves * remaining_moves);
+            }
+            alpha = alpha.max(score);
+        }
+
+        if let Some((key, orientation)) = table_key {
+            self.table.set(key, (alpha - MIN_SCORE + 1) as u8 | orientation); // save the upper bound of the position score
+        }
+
+        alpha
+    }
+}
+
+// Returns the first column with the highest score
+fn best_of(scores: &[(Column, i32)]) -> Option<(Column, i32)> {
+    let mut best: Option<(Column, i32)> = None;
+    for &(column, score) in scores {
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((column, score));
+        }
+    }
+    best
+}
+
+// Largest magnitude of the evaluations of solve_to_depth, which is below the score of any game decided
+// by the move limit: winning with the last move before the limit scores one more
+fn evaluation_bound<B: Board>(move_limit: u32) -> i32 {
+    score::<B>(move_limit.saturating_sub(1)) - 1
+}
+
+// Evaluates a position at the move limit of solve_to_depth by the threats of the player to move minus
+// those of their opponent
+fn evaluate_at_limit<B: Board>(position: &B, move_limit: u32) -> i32 {
+    let player = position.current_player();
+    let opponent = if player == Player::One { Player::Two } else { Player::One };
+    let bound = evaluation_bound::<B>(move_limit);
+    (position.threats(player) as i32 - position.threats(opponent) as i32).clamp(-bound, bound)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::vec;
+    #[cfg(feature = "std")]
+    use rand::rngs::StdRng;
+    #[cfg(feature = "std")]
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_score() {
+        // Win on 4th stone of player 1 -> each player played 3 so far
+        assert_eq!(score::<BitBoard>(6), 18);
         // 4th stone of player 2 -> P1 played 4, P2 played 3
-        assert_eq!(score(7), 18);
+        assert_eq!(score::<BitBoard>(7), 18);
 
         // 18th stone of player 1 -> P1 played 17, P2 played 17
-        assert_eq!(score(34), 4);
+        assert_eq!(score::<BitBoard>(34), 4);
         // 18th stone of player 2 -> P1 played 18, P2 played 17
-        assert_eq!(score(35), 4);
+        assert_eq!(score::<BitBoard>(35), 4);
+    }
+
+    #[test]
+    fn test_score_bounds() {
+        let mut solver = Solver::new();
+        assert_eq!(solver.score_bounds(&BitBoard::new()), (-21, 21));
+        assert_eq!(solver.score_bounds(&SizedBitBoard::<5, 4>::new()), (-10, 10));
+
+        // Every score is within the bounds, the win on the next move reaches the highest
+        for notation in ["435462", "2252576253462244111563365343671351441", "17516442226766"] {
+            let board = BitBoard::from_notation(notation);
+            let (min, max) = solver.score_bounds(&board);
+            let score = solver.solve(&board).score;
+            assert!(min <= score && score <= max, "{notation}");
+            assert_eq!(score == max, board.can_win_in_one_move(), "{notation}");
+        }
+
+        // One move before the board is full, only a draw or a win on the last move are possible
+        let board = BitBoard::from_notation("34527211257742447772211614456636135333556");
+        assert_eq!(solver.score_bounds(&board), (0, 1));
+        solver.set_draw_value(-1);
+        assert_eq!(solver.score_bounds(&board), (-1, 1));
+    }
+
+    // A board that can't be Copy because it records the moves played, and can pretend to have no key
+    #[derive(Clone)]
+    struct HistoryBoard {
+        board: BitBoard,
+        history: Vec<Column>,
+        keyless: bool,
+    }
+
+    impl HistoryBoard {
+        fn from_notation(notation: &str, keyless: bool) -> Self {
+            HistoryBoard {
+                board: BitBoard::from_notation(notation),
+                history: notation.chars().map(|c| Column::try_from(c).unwrap()).collect(),
+                keyless,
+            }
+        }
+    }
+
+    impl Board for HistoryBoard {
+        fn is_playable(&self, column: Column) -> bool {
+            self.board.is_playable(column)
+        }
+
+        fn is_winning(&self, column: Column) -> bool {
+            self.board.is_winning(column)
+        }
+
+        fn play(&mut self, column: Column) -> u32 {
+            self.history.push(column);
+            self.board.play(column)
+        }
+
+        fn unplay(&mut self, column: Column) -> u32 {
+            assert_eq!(self.history.pop(), Some(column));
+            self.board.unplay(column)
+        }
+
+        fn number_of_moves(&self) -> u32 {
+            self.board.number_of_moves()
+        }
+
+        fn current_player(&self) -> Player {
+            self.board.current_player()
+        }
+
+        fn has_key(&self) -> bool {
+            !self.keyless
+        }
+
+        fn key(&self) -> u64 {
+            assert!(self.has_key());
+            self.board.key()
+        }
+
+        fn canonical_key(&self) -> u64 {
+            assert!(self.has_key());
+            self.board.canonical_key()
+        }
+
+        fn zobrist_key(&self) -> u64 {
+            self.board.zobrist_key()
+        }
+
+        fn status(&self) -> GameStatus {
+            self.board.status()
+        }
+
+        fn history(&self) -> &[Column] {
+            self.board.history()
+        }
+
+        fn possible_nonlosing_moves(&self) -> BitBoardField {
+            self.board.possible_nonlosing_moves()
+        }
+
+        fn can_win_in_one_move(&self) -> bool {
+            self.board.can_win_in_one_move()
+        }
+
+        fn score_move(&self, column: Column) -> ScoredMove {
+            self.board.score_move(column)
+        }
+
+        fn score_move_detailed(&self, column: Column) -> DetailedMove {
+            self.board.score_move_detailed(column)
+        }
+
+        fn landing_rows(&self) -> [Option<usize>; WIDTH] {
+            self.board.landing_rows()
+        }
+
+        fn cell(&self, row: usize, column: usize) -> Cell {
+            self.board.cell(row, column)
+        }
+
+        fn creates_opponent_fork(&self, column: Column) -> bool {
+            self.board.creates_opponent_fork(column)
+        }
+
+        fn has_double_threat(&self) -> bool {
+            self.board.has_double_threat()
+        }
+
+        fn threats(&self, player: Player) -> u32 {
+            self.board.threats(player)
+        }
+    }
+
+    #[test]
+    fn test_solve_non_copy_board() {
+        let board = HistoryBoard::from_notation("5554224333234511764415115", false);
+
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve(&board).score, 4);
+        assert_eq!(solver.solve(&board.board).score, 4);
+    }
+
+    #[test]
+    fn test_solve_without_key() {
+        let board = HistoryBoard::from_notation("5554224333234511764415115", true);
+
+        // Nothing is stored in the table, a second search doesn't get any faster
+        let mut solver = Solver::new();
+        let first = solver.solve(&board);
+        let second = solver.solve(&board);
+        assert_eq!(first.score, 4);
+        assert_eq!(second.score, 4);
+        assert_eq!(first.nodes_searched, second.nodes_searched);
+        assert!(second.nodes_searched > solver.solve(&board.board).nodes_searched);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_expected_score_vs_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut solver = Solver::new();
+
+        // Won position for the current player, optimal play can't lose against a random opponent
+        let board = BitBoard::from_notation("7422341735647741166133573473242566");
+        assert!(solver.expected_score_vs_random(&board, 20, &mut rng) > 0.9);
+
+        // Two moves from a full board, the last move can't win
+        let board = BitBoard::from_notation("6754245773172233714525132676434432661561");
+        assert_eq!(solver.expected_score_vs_random(&board, 5, &mut rng), 0.5);
+    }
+
+    #[test]
+    fn test_draw_value() {
+        // Playing F draws, playing E loses in the last move
+        let board = BitBoard::from_notation("345272112577424477722116144566361353335");
+
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve(&board).score, 0);
+        assert_eq!(solver.best_move(&board), Some((Column::F, 0)));
+
+        // A draw is now as bad as the late loss, and E comes first in the move order
+        solver.set_draw_value(-1);
+        assert_eq!(solver.solve(&board).score, -1);
+        assert_eq!(solver.best_move(&board), Some((Column::E, -1)));
+
+        solver.set_draw_value(0);
+        assert_eq!(solver.best_move(&board), Some((Column::F, 0)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_solve_traced() {
+        let position = BitBoard::from_notation("17516442226766");
+        let expected = Solver::new().solve(&position);
+        let mut out = Vec::new();
+        let result = Solver::new().solve_traced(&position, u32::MAX, &mut out).unwrap();
+        assert_eq!((result.score, result.nodes_searched), (expected.score, expected.nodes_searched));
+
+        let nodes: Vec<serde_json::Value> = out
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(nodes.len(), result.nodes_searched);
+        let mut ids: Vec<_> = nodes.iter().map(|node| node["id"].as_u64().unwrap() as usize).collect();
+        let mut by_id = vec![None; nodes.len()];
+        for (i, &id) in ids.iter().enumerate() {
+            by_id[id] = Some(i);
+        }
+        ids.sort();
+        assert!(ids.iter().copied().eq(0..nodes.len()));
+        let roots = nodes.iter().filter(|node| node["parent"].is_null()).count();
+        assert_eq!(roots, Solver::new().solve_iter(&position).count());
+        for (i, node) in nodes.iter().enumerate() {
+            let depth = node["depth"].as_u64().unwrap();
+            assert_eq!(node["parent"].is_null(), depth == 0);
+            assert_eq!(node["move"].is_null(), depth == 0);
+            if !node["cutoff"].is_null() {
+                assert!(node["score"].as_i64() >= node["beta"].as_i64());
+            }
+            // Children are written before their parent, but entered after it
+            if let Some(parent) = node["parent"].as_u64() {
+                let position = by_id[parent as usize].unwrap();
+                assert_eq!(nodes[position]["depth"].as_u64(), Some(depth - 1));
+                assert!(position > i && parent < node["id"].as_u64().unwrap());
+            }
+        }
+
+        // Deeper nodes are searched but not written
+        let mut shallow = Vec::new();
+        let result = Solver::new().solve_traced(&position, 1, &mut shallow).unwrap();
+        assert_eq!((result.score, result.nodes_searched), (expected.score, expected.nodes_searched));
+        assert!(shallow.len() < out.len());
+        for line in shallow.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()) {
+            let node: serde_json::Value = serde_json::from_slice(line).unwrap();
+            assert!(node["depth"].as_u64().unwrap() <= 1);
+        }
+
+        let mut buffer = [0; 16];
+        let error = Solver::new().solve_traced(&position, 1, &mut buffer.as_mut_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_solve_iter() {
+        let mut solver = Solver::new();
+        for notation in ["5554224333234511764415115", "1233722555341451114725221333", "274552224131661"] {
+            let board = BitBoard::from_notation(notation);
+            let windows: Vec<_> = solver.solve_iter(&board).collect();
+            assert!(windows.windows(2).all(|w| w[1].0 >= w[0].0 && w[1].1 <= w[0].1 && w[1] != w[0]));
+
+            let (min, max) = *windows.last().unwrap();
+            assert_eq!(min, max);
+            solver.clear();
+            assert_eq!(solver.solve(&board).score, min);
+        }
+
+        // Immediate win, nothing to search
+        let board = BitBoard::from_notation("435462");
+        let windows: Vec<_> = solver.solve_iter(&board).collect();
+        assert_eq!(windows, vec![(18, 18)]);
+    }
+
+    #[test]
+    fn test_outcome_for() {
+        assert_eq!(outcome_for(3, Player::One, Player::One), Outcome::Win);
+        assert_eq!(outcome_for(3, Player::One, Player::Two), Outcome::Loss);
+        assert_eq!(outcome_for(-2, Player::One, Player::One), Outcome::Loss);
+        assert_eq!(outcome_for(-2, Player::One, Player::Two), Outcome::Win);
+
+        assert_eq!(outcome_for(1, Player::Two, Player::Two), Outcome::Win);
+        assert_eq!(outcome_for(1, Player::Two, Player::One), Outcome::Loss);
+        assert_eq!(outcome_for(-5, Player::Two, Player::Two), Outcome::Loss);
+        assert_eq!(outcome_for(-5, Player::Two, Player::One), Outcome::Win);
+
+        assert_eq!(outcome_for(0, Player::One, Player::Two), Outcome::Draw);
+        assert_eq!(outcome_for(0, Player::Two, Player::Two), Outcome::Draw);
+    }
+
+    #[test]
+    fn test_find_any_win() {
+        let mut solver = Solver::new();
+
+        // Immediate win
+        let board = BitBoard::from_notation("435462");
+        assert_eq!(solver.find_any_win(&board), Some(Column::G));
+
+        // Won position, the returned move must keep the win
+        let board = BitBoard::from_notation("7422341735647741166133573473242566");
+        let column = solver.find_any_win(&board).unwrap();
+        let mut next = board;
+        next.play(column);
+        assert!(solver.solve(&next).score < 0);
+
+        // Lost and drawn positions
+        let board = BitBoard::from_notation("2252576253462244111563365343671351441");
+        assert_eq!(solver.find_any_win(&board), None);
+        let board = BitBoard::from_notation("23163416124767223154467471272416755633");
+        assert_eq!(solver.find_any_win(&board), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Finding a win needs the default draw value")]
+    fn test_find_any_win_draw_value() {
+        // A drawing move would be taken for a win, since a draw then scores like a loss for the opponent
+        let mut solver = Solver::new();
+        solver.set_draw_value(1);
+        solver.find_any_win(&BitBoard::from_notation("23163416124767223154467471272416755633"));
+    }
+
+    #[test]
+    fn test_canonical_keys_same_scores() {
+        let positions = [
+            "2252576253462244111563365343671351441",
+            "7422341735647741166133573473242566",
+            "23163416124767223154467471272416755633",
+            "65214673556155731566316327373221417",
+            "5554224333234511764415115",
+            "1233722555341451114725221333",
+        ];
+
+        let mut canonical = Solver::new();
+        let mut plain = Solver::new();
+        plain.set_canonical_keys(false);
+        for notation in positions {
+            let board = BitBoard::from_notation(notation);
+            assert_eq!(
+                canonical.solve(&board).score,
+                plain.solve(&board).score,
+                "{notation}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut solver = Solver::new();
+        // C and G win on the next move
+        assert_eq!(solver.evaluate(&BitBoard::from_notation("445566")), Evaluation::Win { in_moves: 1 });
+        // Either player's last piece wins: the current player has 17 pieces and wins with the 21st
+        let board = BitBoard::from_notation("7422341735647741166133573473242566");
+        assert_eq!(solver.evaluate(&board), Evaluation::Win { in_moves: 4 });
+        assert_eq!(Evaluation::from_score(-1, &board), Evaluation::Loss { in_moves: 4 });
+        assert_eq!(solver.evaluate(&BitBoard::from_notation("165746146225")), Evaluation::Loss { in_moves: 5 });
+        assert_eq!(solver.evaluate(&BitBoard::from_notation("361576644653612")), Evaluation::Draw);
+
+        // X threatens both sides of its three pieces, O loses on the next move whatever it plays
+        assert_eq!(solver.evaluate(&BitBoard::from_notation("31415")), Evaluation::Loss { in_moves: 1 });
+        // The second player wins with their last piece, the first player with their 21st one
+        let odd = BitBoard::from_notation("4");
+        assert_eq!(Evaluation::from_score(1, &odd), Evaluation::Win { in_moves: 21 });
+        assert_eq!(Evaluation::from_score(-1, &odd), Evaluation::Loss { in_moves: 20 });
+
+        // The number of moves matches the moves played along the principal variation
+        let board = BitBoard::from_notation("17516442226766");
+        let Evaluation::Win { in_moves } = solver.evaluate(&board) else { panic!() };
+        assert_eq!(solver.principal_variation(&board).len() as u32, 2 * in_moves - 1);
+    }
+
+    #[test]
+    fn test_lower_bound_entries() {
+        let mut board = BitBoard::from_notation("17516442226766");
+        let mut solver = Solver::new();
+        let score = solver.solve_impl(&mut board, &mut Search::default(), 7, 8);
+        assert!(score >= 8);
+
+        // The fail-high stored a lower bound, which cuts the same search off at the root
+        let mut search = Search::default();
+        assert!(solver.solve_impl(&mut board, &mut search, 7, 8) >= 8);
+        assert_eq!(search.nodes_searched, 1);
+        // A window above the bound still needs a search
+        let mut search = Search::default();
+        solver.solve_impl(&mut board, &mut search, 8, 9);
+        assert!(search.nodes_searched > 1);
+    }
+
+    #[test]
+    fn test_best_moves() {
+        let board = BitBoard::from_notation("17516442226766");
+        let mirror = BitBoard::from_notation("71372446662122");
+        // The column stored for a position, in the orientation of the position
+        let best_move = |solver: &Solver, board: &BitBoard| {
+            let column = solver.best_moves.get(board.canonical_key())? as usize - 1;
+            Some(if board.canonical_key() != board.key() { WIDTH - 1 - column } else { column })
+        };
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve(&board).score, 8);
+        let column = best_move(&solver, &board).unwrap();
+        assert!(column < WIDTH);
+
+        // The stored moves are mirrored along with the position, which shares the entry
+        assert_eq!(mirror.canonical_key(), board.canonical_key());
+        assert_eq!(best_move(&solver, &mirror), Some(WIDTH - 1 - column));
+        assert_eq!(solver.solve(&mirror).score, 8);
+        assert_eq!(best_move(&solver, &mirror), Some(WIDTH - 1 - column));
+        let mut fresh = Solver::new();
+        assert_eq!(fresh.solve(&mirror).score, 8);
+        assert_eq!(best_move(&fresh, &mirror), Some(WIDTH - 1 - column));
+
+        solver.clear();
+        assert_eq!(solver.best_moves.get(board.canonical_key()), None);
+
+        // Same scores with fewer nodes: 374583 instead of 375286 on these positions, each solved from scratch
+        let mut plain = Solver::with_table_bits(20);
+        plain.set_best_moves(false);
+        solver = Solver::with_table_bits(20);
+        let (mut nodes, mut plain_nodes) = (0, 0);
+        for line in include_str!("../benchmarks/Test_L1_R1.txt").lines().take(100) {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            let score: i32 = score.parse().unwrap();
+            solver.clear();
+            plain.clear();
+            let (result, plain_result) = (solver.solve(&board), plain.solve(&board));
+            assert_eq!((result.score, plain_result.score), (score, score), "{notation}");
+            nodes += result.nodes_searched;
+            plain_nodes += plain_result.nodes_searched;
+        }
+        assert!(nodes < plain_nodes, "{nodes} nodes with the best moves, {plain_nodes} without them");
+        // They are still stored, only not searched first
+        plain.clear();
+        assert_eq!(plain.solve(&board).score, 8);
+        assert!(plain.best_moves.get(board.canonical_key()).is_some());
+    }
+
+    #[test]
+    fn test_history_heuristic() {
+        // Same scores with fewer nodes: 374583 instead of 453241 on these positions, each solved from scratch
+        let mut solver = Solver::with_table_bits(20);
+        let mut plain = Solver::with_table_bits(20);
+        plain.set_history_heuristic(false);
+        let (mut nodes, mut plain_nodes) = (0, 0);
+        for line in include_str!("../benchmarks/Test_L1_R1.txt").lines().take(100) {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            let score: i32 = score.parse().unwrap();
+            solver.clear();
+            plain.clear();
+            let (result, plain_result) = (solver.solve(&board), plain.solve(&board));
+            assert_eq!((result.score, plain_result.score), (score, score), "{notation}");
+            nodes += result.nodes_searched;
+            plain_nodes += plain_result.nodes_searched;
+        }
+        assert!(nodes < plain_nodes, "{nodes} nodes with the history heuristic, {plain_nodes} without it");
+
+        assert!(solver.history.iter().any(|&weight| weight > 0));
+        assert_eq!(plain.history, [0; WIDTH * (HEIGHT + 1)]);
+        solver.clear();
+        assert_eq!(solver.history, [0; WIDTH * (HEIGHT + 1)]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_effective_branching_factor() {
+        let result = SolveResult::new(0, 1000);
+        assert!((result.effective_branching_factor(3) - 10.0).abs() < 1e-9);
+        assert_eq!(result.effective_branching_factor(1), 1000.0);
+        assert_eq!(result.effective_branching_factor(0), 0.0);
+        assert_eq!(result.nodes_per_second(), 0.0);
+
+        let result = SolveResult {
+            elapsed: Duration::from_millis(500),
+            ..result
+        };
+        assert_eq!(result.nodes_per_second(), 2000.0);
+    }
+
+    #[test]
+    fn test_move_order() {
+        let board = BitBoard::from_notation("7441746225252552");
+        let result = Solver::new().solve(&board);
+
+        let mut solver = Solver::new();
+        solver.set_move_order(COLUMN_ORDER);
+        assert_eq!(solver.solve(&board).nodes_searched, result.nodes_searched);
+
+        let mut solver = Solver::new();
+        solver.set_move_order([Column::A, Column::B, Column::C, Column::D, Column::E, Column::F, Column::G]);
+        let left_to_right = solver.solve(&board);
+        assert_eq!(left_to_right.score, result.score);
+        assert_ne!(left_to_right.nodes_searched, result.nodes_searched);
+
+        // Smaller boards skip the columns they don't have
+        let small = SizedBitBoard::<5, 4>::from_notation("3322");
+        let result = Solver::new().solve(&small);
+        let mut solver = Solver::new();
+        solver.set_move_order([Column::G, Column::F, Column::A, Column::B, Column::C, Column::D, Column::E]);
+        assert_eq!(solver.solve(&small).score, result.score);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing a column")]
+    fn test_move_order_missing_column() {
+        Solver::new().set_move_order([Column::A; WIDTH]);
+    }
+
+    #[test]
+    fn test_solver_builder() {
+        let solver = SolverBuilder::default().build();
+        assert_eq!(solver.table().size(), Solver::new().table().size());
+        assert!(solver.history_heuristic && solver.book.is_none());
+
+        let solver = SolverBuilder::default()
+            .table_bits(20)
+            .use_opening_book(OpeningBook::default())
+            .move_ordering(MoveOrdering::Static)
+            .build();
+        assert_eq!(solver.table().size(), TranspositionTable::with_bits(20).size());
+        assert!(!solver.history_heuristic && solver.book.is_some());
+    }
+
+    #[test]
+    fn test_mirror_hits() {
+        // symmetric position: every line of play has a mirror image reaching the same entries
+        let board = BitBoard::from_notation("612773152266216737512266");
+
+        let mut canonical = Solver::new();
+        let result = canonical.solve(&board);
+        assert!(result.mirror_hits > 0);
+
+        let mut plain = Solver::new();
+        plain.set_canonical_keys(false);
+        let plain_result = plain.solve(&board);
+        assert_eq!(plain_result.mirror_hits, 0);
+        assert_eq!(plain_result.score, result.score);
+    }
+
+    #[test]
+    fn test_best_move_tie_break() {
+        // On symmetric positions, every column ties with its mirror image, and the left one is chosen
+        let mut solver = Solver::new();
+        let board = BitBoard::from_notation("612773152266216737512266");
+        let scores = solver.analyze(&board);
+        let (column, score) = solver.best_move(&board).unwrap();
+        assert_eq!(Some(score), scores.into_iter().flatten().max());
+        let expected = generate_preference_order(WIDTH).into_iter().find(|&c| scores[c as usize] == Some(score));
+        assert_eq!(Some(column), expected);
+        assert!(column as usize <= Column::D as usize, "{column:?}");
+
+        // The empty 4x4 board is symmetric too, and its two center columns are as close to the center
+        let board = SizedBitBoard::<4, 4>::new();
+        let scores = solver.analyze(&board);
+        assert_eq!(scores[1], scores[2]);
+        let (column, _) = solver.best_move(&board).unwrap();
+        assert!(column != Column::C && column != Column::D);
+        assert_eq!(solver.best_move(&board).unwrap().0, column);
+    }
+
+    #[test]
+    fn test_smaller_boards() {
+        assert_eq!(generate_move_order(4)[..4], [Column::B, Column::C, Column::A, Column::D]);
+        assert_eq!(generate_move_order(WIDTH), COLUMN_ORDER);
+        assert_eq!(generate_preference_order(4)[..4], [Column::B, Column::C, Column::A, Column::D]);
+        assert_eq!(
+            generate_preference_order(WIDTH),
+            [Column::D, Column::C, Column::E, Column::B, Column::F, Column::A, Column::G]
+        );
+        assert_eq!(generate_preference_order(5), [Column::C, Column::B, Column::D, Column::A, Column::E, Column::F, Column::G]);
+
+        let mut solver = Solver::new();
+        // 4x4 Connect 4 is a draw
+        let result = solver.solve(&SizedBitBoard::<4, 4>::new());
+        assert_eq!(result.score, 0);
+
+        // Only the four columns can be played, and the vertical win is scored from the 16 cells
+        let board = SizedBitBoard::<4, 4>::try_from_notation("121212").unwrap();
+        assert!(!board.is_playable(Column::E));
+        assert_eq!(solver.solve(&board).score, (4 * 4 + 1 - 6) / 2);
+        assert_eq!(solver.best_move(&board), Some((Column::A, 5)));
+
+        // The keys of the 4x4 positions are also keys of 5x4 positions, whose scores can differ
+        assert_eq!(solver.solve(&SizedBitBoard::<4, 4>::from_notation("12344321")).score, 0);
+        assert_eq!(solver.solve(&SizedBitBoard::<5, 4>::from_notation("12344321")).score, -1);
+        // and so are the keys of Connect 5 positions
+        let notation = "7422341735647741166133573473242566";
+        assert_eq!(solver.solve(&BitBoard::from_notation(notation)).score, 1);
+        assert_eq!(solver.solve(&SizedBitBoard::<WIDTH, HEIGHT, 5>::from_notation(notation)).score, -1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_smaller_boards_minimax() {
+        // Same scores as a plain minimax over every move, from random positions of a 5x4 board
+        let mut solver = Solver::new();
+        fn minimax(board: &SizedBitBoard<5, 4>) -> i32 {
+            if board.can_win_in_one_move() {
+                return (5 * 4 + 1 - board.number_of_moves() as i32) / 2;
+            }
+            let children = COLUMN_ORDER.into_iter().filter(|&column| board.is_playable(column)).map(|column| {
+                let mut child = *board;
+                child.play(column);
+                -minimax(&child)
+            });
+            children.max().unwrap_or(0)
+        }
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut n_positions = 0;
+        while n_positions < 20 {
+            let mut board = SizedBitBoard::<5, 4>::new();
+            while board.number_of_moves() < 10 {
+                match board.random_move(&mut rng) {
+                    Some(column) if !board.is_winning(column) => board.play(column),
+                    _ => break,
+                };
+            }
+            if board.number_of_moves() == 10 {
+                assert_eq!(solver.solve(&board).score, minimax(&board), "{}", board.to_notation());
+                n_positions += 1;
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_with_saved_table() {
+        let board = BitBoard::from_notation("612773152266216737512266");
+        let mut solver = Solver::with_table_bits(17);
+        let result = solver.solve(&board);
+
+        let mut file = Vec::new();
+        solver.table().save(&mut file).unwrap();
+        let mut warm = Solver::with_table(TranspositionTable::load(file.as_slice()).unwrap());
+        let warm_result = warm.solve(&board);
+        assert_eq!(warm_result.score, result.score);
+        assert!(warm_result.nodes_searched < result.nodes_searched);
+    }
+
+    #[test]
+    fn test_opening_book() {
+        let board = BitBoard::from_notation("612773152266216737512266");
+        let result = Solver::new().solve(&board);
+
+        // Booked positions are solved without searching
+        let mut solver = Solver::with_table_bits(17);
+        solver.set_opening_book(Some(OpeningBook::generate_from(&board, 1)));
+        let book_result = solver.solve(&board);
+        assert_eq!((book_result.score, book_result.nodes_searched), (result.score, 0));
+        assert!(book_result.complete);
+        assert_eq!(solver.best_move(&board).map(|(_, score)| score), Some(result.score));
+        assert_eq!(solver.table_stats(), TableStats::default());
+
+        // The book only holds exact scores
+        solver.set_draw_value(-1);
+        assert!(solver.solve(&board).nodes_searched > 0);
+    }
+
+    #[test]
+    fn test_table_stats() {
+        let mut solver = Solver::new();
+        let result = solver.solve(&BitBoard::from_notation("612773152266216737512266"));
+        let stats = solver.table_stats();
+        assert!(stats.hits > 0);
+        assert!(stats.misses > 0);
+        assert_eq!(result.table_hits, stats.hits);
+
+        // Results count the hits of their own search only
+        let result = solver.solve(&BitBoard::from_notation("61277315226621673751226"));
+        assert_eq!(result.table_hits, solver.table_stats().hits - stats.hits);
+
+        solver.clear();
+        assert_eq!(solver.table_stats(), TableStats::default());
+    }
+
+    #[test]
+    fn test_is_zugzwang() {
+        let mut solver = Solver::new();
+
+        // Whoever moves first loses, passing would turn the loss into a win
+        let board = BitBoard::from_notation("655633662174357616777327512432315112");
+        assert!(solver.solve(&board).score < 0);
+        assert!(solver.is_zugzwang(&board));
+
+        // Here moving is better than passing
+        assert!(!solver.is_zugzwang(&BitBoard::from_notation("2252576253462244111563365343671351441")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Zugzwang needs the default draw value")]
+    fn test_is_zugzwang_draw_value() {
+        let mut solver = Solver::new();
+        solver.set_draw_value(1);
+        solver.is_zugzwang(&BitBoard::from_notation("2252576253462244111563365343671351441"));
+    }
+
+    #[test]
+    fn test_solve_to_depth() {
+        let mut solver = Solver::new();
+
+        // Won with the third move from now, after the opponent blocks one of two threats
+        let board = BitBoard::from_notation("4455");
+        let result = solver.solve_to_depth(&board, 3);
+        assert_eq!((result.score, result.heuristic), (18, false));
+        let result = solver.solve_to_depth(&board, 2);
+        assert!(result.heuristic && result.complete);
+        assert!(result.score.abs() < 18);
+
+        // Won with the 11th move from now
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let result = solver.solve_to_depth(&board, 11);
+        assert_eq!((result.score, result.heuristic), (4, false));
+        assert!(solver.solve_to_depth(&board, 10).heuristic);
+
+        // The evaluation of the position itself
+        let board = BitBoard::from_notation("443372422");
+        let result = solver.solve_to_depth(&board, 0);
+        assert_eq!((result.score, result.heuristic), (2, true));
+        assert_eq!(solver.solve_to_depth(&BitBoard::new(), 0).score, 0);
+        assert!(solver.solve_to_depth(&BitBoard::new(), 8).heuristic);
+        let result = solver.solve_to_depth(&BitBoard::from_notation("435462"), 0);
+        assert_eq!((result.score, result.heuristic), (18, false));
+
+        // Searching to the end of the game is exact, and the table is left valid for regular searches
+        let board = BitBoard::from_notation("2252576253462244111563365343671351441");
+        let score = solver.solve(&board).score;
+        let result = solver.solve_to_depth(&board, 5);
+        assert_eq!((result.score, result.heuristic), (score, false));
+        assert!(solver.solve_to_depth(&board, 1).heuristic);
+        assert_eq!(solver.solve(&board).score, score);
+    }
+
+    #[test]
+    fn test_solve_with_horizon() {
+        let mut solver = Solver::new();
+
+        // Won on the next move
+        let board = BitBoard::from_notation("435462");
+        assert_eq!(solver.solve_with_horizon(&board, 0), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 1), HorizonResult::Conclusive(18));
+
+        // Won with the 11th move from now
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        assert_eq!(solver.solve(&board).score, 4);
+        assert_eq!(solver.solve_with_horizon(&board, 10), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 11), HorizonResult::Conclusive(4));
+        assert_eq!(solver.solve_with_horizon(&board, 20), HorizonResult::Conclusive(4));
+
+        // Two threats, lost on the opponent's next move
+        let board = BitBoard::from_notation("44553");
+        assert_eq!(solver.solve_with_horizon(&board, 1), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 2), HorizonResult::Conclusive(-18));
+
+        // A drawn position is only conclusive once the horizon reaches the end of the game
+        let board = BitBoard::from_notation("345272112577424477722116144566361353335");
+        assert_eq!(solver.solve(&board).score, 0);
+        assert_eq!(solver.solve_with_horizon(&board, 2), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 3), HorizonResult::Conclusive(0));
+
+        // A draw value doesn't make a cut-off conclusive
+        solver.set_draw_value(1);
+        assert_eq!(solver.solve_with_horizon(&board, 2), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 3), HorizonResult::Conclusive(1));
+        let board = BitBoard::from_notation("44553");
+        assert_eq!(solver.solve_with_horizon(&board, 1), HorizonResult::Inconclusive);
+        assert_eq!(solver.solve_with_horizon(&board, 2), HorizonResult::Conclusive(-18));
+        solver.set_draw_value(0);
+
+        // The table is left valid for regular searches, and keeps their entries
+        let board = BitBoard::from_notation("2252576253462244111563365343671351441");
+        let score = solver.solve(&board).score;
+        let entry = solver.table.get(board.canonical_key());
+        assert!(entry.is_some());
+        solver.solve_with_horizon(&board, 1);
+        assert_eq!(solver.table.get(board.canonical_key()), entry);
+        assert_eq!(solver.solve(&board).score, score);
+    }
+
+    #[test]
+    fn test_reply_analysis() {
+        let mut solver = Solver::new();
+
+        // The opponent threatens to win in F, only playing there myself holds
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let analysis = solver.reply_analysis(&board);
+        assert_eq!(analysis.len(), 6);
+        for (column, reply, score) in analysis {
+            if column == Column::F {
+                assert_eq!(score, solver.solve(&board).score);
+            } else {
+                assert_eq!(reply, Column::F);
+                assert_eq!(score, -8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_best_move() {
+        let mut solver = Solver::new();
+
+        // Only F holds, see test_reply_analysis
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        assert_eq!(solver.best_move(&board), Some((Column::F, 4)));
+
+        // Every move loses, a move is still returned
+        let board = BitBoard::from_notation("44553");
+        assert_eq!(solver.best_move(&board).map(|(_, score)| score), Some(-18));
+
+        let full = BitBoard::from_notation("675424577317223371452513267643443266156151");
+        assert_eq!(full.number_of_moves() as usize, WIDTH * HEIGHT);
+        assert_eq!(solver.best_move(&full), None);
+    }
+
+    #[test]
+    fn test_principal_variation() {
+        let mut solver = Solver::new();
+
+        // Score 4 for the second player: they win with their 6th stone from now, the 11th move of the line
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let line = solver.principal_variation(&board);
+        assert_eq!(line.len(), 11);
+        let mut position = board;
+        for &column in &line[..line.len() - 1] {
+            assert_eq!(solver.best_move(&position).map(|(best, _)| best), Some(column));
+            position.play(column);
+        }
+        assert!(position.is_winning(line[10]));
+
+        // Every move loses right away
+        let line = solver.principal_variation(&BitBoard::from_notation("44553"));
+        assert_eq!(line.len(), 2);
+
+        // A drawn line fills the board
+        let board = BitBoard::from_notation("67542457731722337145251326764344326615");
+        assert_eq!(solver.solve(&board).score, 0);
+        assert_eq!(solver.principal_variation(&board).len(), 4);
+        let full = BitBoard::from_notation("675424577317223371452513267643443266156151");
+        assert!(solver.principal_variation(&full).is_empty());
+    }
+
+    #[test]
+    #[ignore = "solving the empty board takes too long for the regular test run"]
+    fn test_best_move_empty_board() {
+        let mut solver = Solver::new();
+        assert_eq!(solver.best_move(&BitBoard::new()), Some((Column::D, 1)));
+    }
+
+    #[test]
+    fn test_analyze() {
+        let mut solver = Solver::new();
+
+        // E is full, and every column but F lets the opponent win right away
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let scores = solver.analyze(&board);
+        assert_eq!(scores, [Some(-8), Some(-8), Some(-8), Some(-8), None, Some(4), Some(-8)]);
+        assert_eq!(scores.iter().flatten().max(), Some(&solver.solve(&board).score));
+
+        // Only two columns are left to play
+        let board = BitBoard::from_notation("2252576253462244111563365343671351441");
+        let scores = solver.analyze(&board);
+        assert_eq!(scores.iter().flatten().count(), 2);
+        assert_eq!(scores.iter().flatten().max(), Some(&solver.solve(&board).score));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_solve_within() {
+        let mut solver = Solver::new();
+
+        let start = Instant::now();
+        let result = solver.solve_within(&BitBoard::new(), Duration::from_millis(1));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!result.complete);
+        assert!((-21..=21).contains(&result.score));
+
+        // The aborted search leaves the table usable
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let result = solver.solve_within(&board, Duration::from_secs(60));
+        assert!(result.complete);
+        assert_eq!(result.score, 4);
+        assert!(solver.solve(&board).complete);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_solve_cancellable() {
+        let mut solver = Solver::new();
+        let start = Instant::now();
+        assert!(solver.solve_cancellable(&BitBoard::new(), &AtomicBool::new(true)).is_none());
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Cancelled by another thread during the search
+        let cancel = AtomicBool::new(false);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                cancel.store(true, Ordering::Relaxed);
+            });
+            solver.solve_cancellable(&BitBoard::new(), &cancel)
+        });
+        assert!(result.is_none());
+
+        let board = BitBoard::from_notation("5554224333234511764415115");
+        let result = solver.solve_cancellable(&board, &AtomicBool::new(false)).unwrap();
+        assert_eq!((result.score, result.complete), (4, true));
+    }
+
+    #[test]
+    fn test_solve_with_node_limit() {
+        let board = BitBoard::from_notation("1233722555341451114725221333");
+        let full = Solver::new().solve(&board);
+        assert!(full.nodes_searched > 100);
+        assert_eq!((full.min, full.max), (full.score, full.score));
+
+        let limited = Solver::new().solve_with_node_limit(&board, 100);
+        assert!(!limited.complete);
+        assert_eq!(limited.nodes_searched, 100);
+        assert!(limited.min <= full.score && full.score <= limited.max);
+
+        // Deterministic from the same table contents
+        let again = Solver::new().solve_with_node_limit(&board, 100);
+        assert_eq!((again.min, again.max, again.score), (limited.min, limited.max, limited.score));
+
+        let result = Solver::new().solve_with_node_limit(&board, full.nodes_searched);
+        assert!(result.complete);
+        assert_eq!(result.score, full.score);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_solve_parallel() {
+        let mut serial = Solver::new();
+        let mut parallel = Solver::new();
+        for line in include_str!("../benchmarks/Test_L3_R1.txt").lines().take(20) {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            let score: i32 = score.parse().unwrap();
+            assert_eq!(parallel.solve_parallel(&board, 3).score, score, "{notation}");
+            assert_eq!(serial.solve(&board).score, score, "{notation}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_worker() {
+        let mut solver = Solver::with_table_bits(17);
+        solver.set_history_heuristic(false);
+        solver.set_best_moves(false);
+        solver.set_move_order(generate_preference_order(WIDTH));
+        let root = BitBoard::from_notation("7422341735647741166133573473242566");
+        solver.set_opening_book(Some(OpeningBook::generate_from(&root, 1)));
+        let worker = solver.worker();
+        assert_eq!(worker.table.size(), solver.table.size());
+        assert!(!worker.history_heuristic && !worker.best_move_ordering);
+        assert_eq!(worker.move_order, solver.move_order);
+        assert_eq!(worker.book.map(|book| book.len()), solver.book.as_ref().map(|book| book.len()));
+    }
+
+    #[test]
+    fn test_solve_with_progress() {
+        // Takes about 3 * 2^20 nodes to solve
+        let board = BitBoard::from_notation("2416615552");
+        let mut reports = Vec::new();
+        let result = Solver::new().solve_with_progress(&board, |nodes| reports.push(nodes));
+
+        assert!(!reports.is_empty());
+        let expected: Vec<_> = (1..=result.nodes_searched / PROGRESS_INTERVAL)
+            .map(|i| i * PROGRESS_INTERVAL)
+            .collect();
+        assert_eq!(reports, expected);
+        assert_eq!(result.score, 4);
+    }
+
+    #[test]
+    fn test_solve_weak() {
+        let mut solver = Solver::new();
+        let lines = include_str!("../benchmarks/Test_L3_R1.txt").lines().take(100);
+        let lines = lines.chain(include_str!("../benchmarks/Test_L2_R1.txt").lines().take(50));
+        for line in lines {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            let expected = match score.parse::<i32>().unwrap().signum() {
+                1 => Outcome::Win,
+                0 => Outcome::Draw,
+                _ => Outcome::Loss,
+            };
+            assert_eq!(solver.solve_weak(&board), expected, "{notation}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Weak solving needs the default draw value")]
+    fn test_solve_weak_draw_value() {
+        // The draw would be returned as a loss
+        let mut solver = Solver::new();
+        solver.set_draw_value(-1);
+        solver.solve_weak(&BitBoard::from_notation("361576644653612"));
+    }
+
+    #[test]
+    fn test_best_move_optimal_speed() {
+        let mut solver = Solver::new();
+
+        // C wins at once, and the center column D wins with the next move
+        let board = BitBoard::from_notation("4576546517561527");
+        let scores = solver.analyze(&board);
+        assert_eq!((scores[Column::C as usize], scores[Column::D as usize]), (Some(13), Some(12)));
+        assert_eq!(solver.best_move_optimal_speed(&board), Some((Column::C, Evaluation::Win { in_moves: 1 })));
+        let mut after_d = board;
+        after_d.play(Column::D);
+        assert_eq!(solver.evaluate(&after_d), Evaluation::Loss { in_moves: 1 });
+
+        // Every move loses, and F holds out the longest
+        let board = BitBoard::from_notation("56224415436274321124");
+        let (column, evaluation) = solver.best_move_optimal_speed(&board).unwrap();
+        assert_eq!(column, Column::F);
+        let Evaluation::Loss { in_moves } = evaluation else { panic!("{evaluation:?}") };
+        for column in Column::iter().filter(|&column| column != Column::F) {
+            let mut next = board;
+            next.play(column);
+            let Evaluation::Win { in_moves: opponent_moves } = solver.evaluate(&next) else { panic!() };
+            assert!(opponent_moves < in_moves, "{column:?}");
+        }
+
+        let full = BitBoard::from_notation("675424577317223371452513267643443266156151");
+        assert_eq!(solver.best_move_optimal_speed(&full), None);
+    }
+
+    #[test]
+    fn test_self_play_iter() {
+        let start = BitBoard::from_notation("5554224333234511764415115");
+        let positions: Vec<_> = Solver::new().self_play_iter(start).collect();
+        assert_eq!(positions[0].0, start);
+
+        // The game follows the principal variation, up to its winning move
+        let line = Solver::new().principal_variation(&start);
+        assert_eq!(positions.len(), line.len());
+        let mut solver = Solver::new();
+        for (i, &(position, score)) in positions.iter().enumerate() {
+            assert_eq!(position.number_of_moves() as usize, 25 + i);
+            assert_eq!(solver.solve(&position).score, score);
+            if let Some(&(next, next_score)) = positions.get(i + 1) {
+                assert_eq!((next.history().last(), next_score), (Some(&line[i]), -score));
+            }
+        }
+        let (last, _) = positions.last().unwrap();
+        assert!(last.is_winning(*line.last().unwrap()));
+
+        assert_eq!(Solver::new().self_play_iter(BitBoard::from_notation("4455667")).count(), 0);
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let root = BitBoard::from_notation("1233722555341451114725221333");
+        let children: Vec<BitBoard> = COLUMN_ORDER
+            .into_iter()
+            .filter(|&column| root.is_playable(column) && !root.is_winning(column))
+            .map(|column| {
+                let mut child = root;
+                child.play(column);
+                child
+            })
+            .collect();
+
+        let results = Solver::new().solve_batch(&children);
+        assert_eq!(results.len(), children.len());
+        let mut cold_nodes = 0;
+        for (child, result) in children.iter().zip(&results) {
+            let cold = Solver::new().solve(child);
+            assert_eq!((result.score, result.complete), (cold.score, true));
+            cold_nodes += cold.nodes_searched;
+        }
+        assert!(results.iter().map(|result| result.nodes_searched).sum::<usize>() < cold_nodes);
+        assert!(Solver::new().solve_batch::<BitBoard>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_solve_window() {
+        let board = BitBoard::from_notation("7422341735647741166133573473242566");
+        let mut solver = Solver::new();
+        let (min, max) = solver.score_bounds(&board);
+        let result = solver.solve_window(&board, min, max);
+        assert_eq!((result.score, result.complete), (1, true));
+
+        // Null windows only tell on which side of their edge the score is
+        let mut solver = Solver::new();
+        let above = solver.solve_window(&board, 0, 1);
+        assert!(!above.complete && above.min >= 1 && above.max == max);
+        let below = solver.solve_window(&board, 1, 2);
+        assert!(!below.complete && below.min == min && below.max <= 1);
+        let result = solver.solve_window(&board, 5, 9);
+        assert!(result.max <= 5);
+
+        // A position won in one move needs no search
+        let result = solver.solve_window(&BitBoard::from_notation("445566"), -1, 0);
+        assert_eq!((result.score, result.complete, result.nodes_searched), (18, true, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty window")]
+    fn test_solve_window_empty() {
+        let _ = Solver::new().solve_window(&BitBoard::new(), 1, 1);
+    }
+
+    #[test]
+    fn test_solve_mtdf() {
+        let lines = include_str!("../benchmarks/Test_L3_R1.txt").lines().take(50);
+        let lines = lines.chain(include_str!("../benchmarks/Test_L2_R1.txt").lines().take(30));
+        let mut nodes = [0; 3];
+        for line in lines {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            let score: i32 = score.parse().unwrap();
+
+            let results = [
+                Solver::new().solve(&board),
+                Solver::new().solve_mtdf(&board, score),
+                Solver::new().solve_mtdf(&board, 0),
+            ];
+            for (result, nodes) in results.iter().zip(&mut nodes) {
+                assert_eq!((result.score, result.complete), (score, true), "{notation}");
+                *nodes += result.nodes_searched;
+            }
+        }
+
+        // An exact guess takes fewer nodes than the bisection or a guess of 0
+        assert!(nodes[1] < nodes[0] && nodes[1] < nodes[2], "{nodes:?}");
+    }
+
+    #[test]
+    fn test_with_table_bits() {
+        let mut solver = Solver::with_table_bits(17);
+        for line in include_str!("../benchmarks/Test_L2_R1.txt").lines().take(20) {
+            let (notation, score) = line.split_once(' ').unwrap();
+            let board = BitBoard::from_notation(notation);
+            assert_eq!(solver.solve(&board).score, score.parse::<i32>().unwrap(), "{notation}");
+        }
+
+        // The table of best moves has an eighth of the entries, but no fewer than the smallest table
+        assert_eq!(solver.best_moves.size(), TranspositionTable::with_bits(17).size());
+        assert_eq!(Solver::with_table_bits(21).best_moves.size(), TranspositionTable::with_bits(18).size());
+        assert_eq!(Solver::new().best_moves.size(), TranspositionTable::with_bits(20).size());
     }
 }
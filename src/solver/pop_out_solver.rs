@@ -0,0 +1,223 @@
+//! Search of the Pop Out variant, where a move either drops a piece or pops one out, see [PopOutBoard]
+use super::COLUMN_ORDER;
+use crate::board::*;
+use alloc::vec::Vec;
+
+/// The result of a Pop Out search, created by [PopOutSolver::solve()]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PopOutResult {
+    /// The player to move wins with the given number of moves, counting the moves of both players
+    Win(u32),
+    /// The opponent wins with the given number of moves, counting the moves of both players
+    Loss(u32),
+    /// Neither player can force a win within the searched moves: the game may be a draw, with a full board
+    /// that can't be popped or by repeating a position, or be decided later
+    Inconclusive,
+}
+
+/// Solver of Pop Out positions, searching both the drops and the pops to a number of moves.
+///
+/// Unlike a game of the regular variant, a game of Pop Out can last forever, so there are no exact scores:
+/// the search proves wins for either player within the searched moves, and prefers the quickest ones.
+/// A position repeating one of the game or of the searched line counts as a draw, since the player who would
+/// win has no need to repeat it. There is no transposition table, whose entries would depend on the line
+/// leading to a position, so the searches are exponential in the number of moves.
+///
+/// ```
+/// use connect4_solver::prelude::*;
+///
+/// let mut solver = PopOutSolver::new();
+/// let board = PopOutBoard::from_notation("44556");
+/// assert_eq!(solver.solve(&board, 4), PopOutResult::Loss(2));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PopOutSolver {
+    max_plies: u32,
+    keys: Vec<u64>, // keys of the positions of the game and of the searched line, to detect repetitions
+    nodes_searched: usize,
+}
+
+impl PopOutSolver {
+    pub fn new() -> Self {
+        PopOutSolver::default()
+    }
+
+    /// Solves the position, searching at most `max_plies` more moves
+    ///
+    /// # Panics
+    /// If the game is already won
+    pub fn solve(&mut self, position: &PopOutBoard, max_plies: u32) -> PopOutResult {
+        match self.search(position, max_plies).0 {
+            score if score > 0 => PopOutResult::Win(max_plies + 1 - score as u32),
+            score if score < 0 => PopOutResult::Loss(max_plies + 1 - (-score) as u32),
+            _ => PopOutResult::Inconclusive,
+        }
+    }
+
+    /// Returns the best move of the player to move, searching at most `max_plies` more moves,
+    /// or None if there are no possible moves or `max_plies` is 0. Among the moves with the same result,
+    /// the drops come before the pops, from the center out.
+    ///
+    /// # Panics
+    /// If the game is already won
+    pub fn best_move(&mut self, position: &PopOutBoard, max_plies: u32) -> Option<PopOutMove> {
+        self.search(position, max_plies).1
+    }
+
+    /// Returns the number of positions searched by the last search
+    pub fn nodes_searched(&self) -> usize {
+        self.nodes_searched
+    }
+
+    // Searches the position from the start of a search, returning its score and best move
+    fn search(&mut self, position: &PopOutBoard, max_plies: u32) -> (i32, Option<PopOutMove>) {
+        assert_eq!(position.winner(), None, "The game is already won");
+        self.max_plies = max_plies;
+        self.nodes_searched = 0;
+        // Replay the game to know the positions it went through
+        let mut game = PopOutBoard::new();
+        self.keys.clear();
+        self.keys.push(game.board().key());
+        for &pop_out_move in position.move_list() {
+            game.play_move(pop_out_move);
+            self.keys.push(game.board().key());
+        }
+        if max_plies == 0 {
+            return (0, None);
+        }
+        let max_score = max_plies as i32;
+        self.negamax(&mut position.clone(), 0, -max_score, max_score)
+    }
+
+    // Returns the score of the position for the player to move after `ply` moves of the search, with its best move:
+    // a win with the nth move of the search scores max_plies + 1 - n, a loss the opposite, and anything else 0.
+    // The position has at least one more move to search, and the previous move didn't win
+    fn negamax(
+        &mut self,
+        position: &mut PopOutBoard,
+        ply: u32,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> (i32, Option<PopOutMove>) {
+        self.nodes_searched += 1;
+        let player = position.current_player();
+        let win_score = |n: u32| self.max_plies as i32 + 1 - n as i32;
+        let drops = COLUMN_ORDER.into_iter().filter(|&column| position.is_playable(column)).map(PopOutMove::Drop);
+        let pops = COLUMN_ORDER.into_iter().filter(|&column| position.can_pop(column)).map(PopOutMove::Pop);
+        let moves: heapless::Vec<PopOutMove, { 2 * WIDTH }> = drops.chain(pops).collect();
+        // a full board whose bottom pieces are the opponent's is a draw
+        let Some(&first_move) = moves.first() else {
+            return (0, None);
+        };
+
+        // Check for a win with this move first, and leave out the pops that make the opponent win right away
+        let mut nonlosing_moves = heapless::Vec::<PopOutMove, { 2 * WIDTH }>::new();
+        for &pop_out_move in &moves {
+            position.play_move(pop_out_move);
+            let winner = position.winner();
+            position.unplay_move(pop_out_move);
+            match winner {
+                Some(winner) if winner == player => return (win_score(ply + 1), Some(pop_out_move)),
+                Some(_) => {}
+                None => nonlosing_moves.push(pop_out_move).unwrap(),
+            }
+        }
+        let Some(&first_nonlosing_move) = nonlosing_moves.first() else {
+            return (-win_score(ply + 1), Some(first_move));
+        };
+        if ply + 1 == self.max_plies {
+            return (0, Some(first_nonlosing_move));
+        }
+
+        // we can't win before our next move
+        let max = win_score(ply + 3);
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return (alpha, Some(first_nonlosing_move));
+            }
+        }
+
+        let mut best = (-win_score(ply + 2), first_nonlosing_move);
+        for pop_out_move in nonlosing_moves {
+            position.play_move(pop_out_move);
+            let key = position.board().key();
+            let score = if self.keys.contains(&key) {
+                0
+            } else {
+                self.keys.push(key);
+                let score = -self.negamax(position, ply + 1, -beta, -alpha).0;
+                self.keys.pop();
+                score
+            };
+            position.unplay_move(pop_out_move);
+            if score > best.0 {
+                best = (score, pop_out_move);
+            }
+            if score >= beta {
+                break;
+            }
+            alpha = alpha.max(score);
+        }
+        (best.0, Some(best.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_solve() {
+        let mut solver = PopOutSolver::new();
+        // Two columns complete the bottom row
+        let board = PopOutBoard::from_notation("445566");
+        assert_eq!(solver.solve(&board, 1), PopOutResult::Win(1));
+        let best_move = solver.best_move(&board, 3).unwrap();
+        assert!(best_move == PopOutMove::Drop(Column::C) || best_move == PopOutMove::Drop(Column::G));
+
+        // The second player can only block one end of the bottom row
+        let board = PopOutBoard::from_notation("44556");
+        assert_eq!(solver.solve(&board, 1), PopOutResult::Inconclusive);
+        assert_eq!(solver.solve(&board, 2), PopOutResult::Loss(2));
+        assert_eq!(solver.solve(&board, 5), PopOutResult::Loss(2));
+
+        // The player who pops wins when both players complete a four-in-a-row
+        let board = PopOutBoard::from_notation("1223344117");
+        assert_eq!(solver.best_move(&board, 1), Some(PopOutMove::Pop(Column::A)));
+
+        assert_eq!(solver.solve(&PopOutBoard::new(), 4), PopOutResult::Inconclusive);
+        assert_eq!(solver.solve(&PopOutBoard::new(), 0), PopOutResult::Inconclusive);
+        assert!(solver.nodes_searched() == 0 && solver.best_move(&PopOutBoard::new(), 0).is_none());
+    }
+
+    #[test]
+    fn test_full_board() {
+        // The board is full, so the regular game is a draw, but every pop lets the opponent win
+        let board = PopOutBoard::from_notation("675424577317223371452513267643443266156151");
+        assert_eq!(Solver::new().solve(board.board()).score, 0);
+        let mut solver = PopOutSolver::new();
+        assert_eq!(solver.solve(&board, 1), PopOutResult::Inconclusive);
+        assert_eq!(solver.solve(&board, 6), PopOutResult::Loss(2));
+        assert!(matches!(solver.best_move(&board, 6), Some(PopOutMove::Pop(_))));
+    }
+
+    #[test]
+    fn test_repetition() {
+        // Popping the two pieces of column D one after the other empties the board again
+        let mut board = PopOutBoard::from_notation("44");
+        board.pop(Column::D);
+        let mut solver = PopOutSolver::new();
+        assert_eq!(solver.solve(&board, 3), PopOutResult::Inconclusive);
+        let nodes = solver.nodes_searched();
+        // the keys of the positions of the game are kept for the next search
+        assert_eq!(solver.keys.len(), 4);
+
+        // Without the positions of the game, the empty board is searched again after the pop
+        solver.keys = alloc::vec![board.board().key()];
+        solver.nodes_searched = 0;
+        assert_eq!(solver.negamax(&mut board.clone(), 0, -3, 3).0, 0);
+        assert!(solver.nodes_searched() > nodes, "{} nodes with the game, {nodes} without it", solver.nodes_searched());
+    }
+}
@@ -1,46 +1,600 @@
+use crate::board::{HEIGHT, WIDTH};
+use alloc::{boxed::Box, vec};
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+/// Counts of the lookups made in a [TranspositionTable] since it was created or cleared
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct TableStats {
+    /// Lookups that found an entry for the key
+    pub hits: u64,
+    /// Lookups that didn't, including collisions
+    pub misses: u64,
+    /// Lookups that found the index taken by an entry for another key
+    pub collisions: u64,
+}
+
+impl TableStats {
+    /// Returns the fraction of lookups that found an entry, or 0 if there were none
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// A value stored in the entries of a [TranspositionTable]. The default value, i.e. 0, marks an empty entry.
+pub trait TableValue: Copy + Default + Eq {
+    /// Size of the value in bytes, which it is saved as in little endian
+    const BYTES: usize;
+    fn write_le(self, bytes: &mut [u8]);
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_table_value {
+    ($($t:ty),*) => {$(
+        impl TableValue for $t {
+            const BYTES: usize = core::mem::size_of::<$t>();
+            fn write_le(self, bytes: &mut [u8]) {
+                bytes.copy_from_slice(&self.to_le_bytes());
+            }
+            fn read_le(bytes: &[u8]) -> Self {
+                Self::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    )*};
+}
+impl_table_value!(u8, u16, u32);
+
 /// A transposition table is a cache of previously computed positions.
 /// It is used to avoid recomputing the same position multiple times.
 /// The table is indexed by a hash of the position, and stores the score of the position for the current player.
-/// The table has a fixed size of 2^23 entries, amounting to 40MB of memory.
-pub struct TranspositionTable {
-    keys: Box<[u32; Self::SIZE]>,
-    scores: Box<[u8; Self::SIZE]>,
+/// The table has about 2^23 entries by default, amounting to 40MB of memory for `u8` values.
+///
+/// The stored values can be any [TableValue]: [TranspositionTable] stores the `u8` values the solver uses,
+/// wider values can hold richer entries at the cost of more memory per entry.
+/// Entries are always replaced, see [DepthPreferredTable] for a table keeping the entries of deeper searches.
+pub struct GenericTranspositionTable<V: TableValue> {
+    keys: Box<[u32]>,
+    scores: Box<[V]>,
+    // Each search thread has its own table, so the counters don't need to be atomic
+    stats: Cell<TableStats>,
 }
 
+/// A transposition table of `u8` values, as used by the solver
+pub type TranspositionTable = GenericTranspositionTable<u8>;
+
 impl TranspositionTable {
-    const SIZE: usize = 8388617; // 1 << 23 + 9
+    const DEFAULT_BITS: u32 = 23;
+    /// The fewest bits accepted by [TranspositionTable::with_bits()], for the index and the 32 stored bits
+    /// to identify the keys of the standard board
+    pub const MIN_BITS: u32 = (WIDTH * (HEIGHT + 1)) as u32 - 32;
+    #[cfg(feature = "std")]
+    const MAX_BITS: u32 = 32; // largest table accepted by load, so a corrupt header can't request a huge allocation
+}
+
+impl<V: TableValue> GenericTranspositionTable<V> {
+    // Tables of u8 values keep the magic they had before the value type was generic, so that files saved
+    // then still load. Tables of other types end with their size, so a table can't be loaded as another type.
+    #[cfg(feature = "std")]
+    const FILE_MAGIC: &'static [u8; 4] = if V::BYTES == 1 { b"C4TT" } else { &[b'C', b'4', b'T', b'0' + V::BYTES as u8] };
 
     pub fn new() -> Self {
+        Self::with_bits(TranspositionTable::DEFAULT_BITS)
+    }
+
+    /// Creates a table with the smallest prime number of entries not below 2^bits, using 4 bytes per entry
+    /// plus the size of the value, i.e. 5 bytes for `u8` values.
+    /// Only 32 bits of each key are stored, so the table must have at least 2^(key bits - 32) entries
+    /// for the index and the stored bits to identify the key.
+    pub fn with_bits(bits: u32) -> Self {
+        assert!(
+            bits >= TranspositionTable::MIN_BITS,
+            "Table must have at least 2^{} entries",
+            TranspositionTable::MIN_BITS
+        );
+        let size = next_prime(1 << bits);
         Self {
-            keys: Box::new([0; Self::SIZE]),
-            scores: Box::new([0; Self::SIZE]),
+            keys: vec![0; size].into_boxed_slice(),
+            scores: vec![V::default(); size].into_boxed_slice(),
+            stats: Cell::default(),
         }
     }
 
-    pub fn get(&self, key: u64) -> Option<u8> {
-        let index = key as usize % Self::SIZE;
+    /// Returns the number of entries of the table
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns the score stored for the key. The index uses all 64 bits of the key, but keys are only told
+    /// apart by their index and lower 32 bits: hashes like [crate::board::Board::zobrist_key()] can collide.
+    /// Stored scores must not be 0, which marks an empty entry:
+    /// otherwise empty entries would match every key with its lower 32 bits unset, like the empty board's.
+    pub fn get(&self, key: u64) -> Option<V> {
+        let index = key as usize % self.keys.len();
         let entry = self.keys[index];
-        if entry == key as u32 {
-            Some(self.scores[index])
+        let score = self.scores[index];
+        let mut stats = self.stats.get();
+        let result = if entry == key as u32 && score != V::default() {
+            stats.hits += 1;
+            Some(score)
         } else {
+            stats.misses += 1;
+            if score != V::default() {
+                stats.collisions += 1;
+            }
             None
-        }
+        };
+        self.stats.set(stats);
+        result
     }
 
-    pub fn set(&mut self, key: u64, score: u8) {
-        let index = key as usize % Self::SIZE;
+    /// Returns the lookup counts since the table was created or last cleared
+    pub fn stats(&self) -> TableStats {
+        self.stats.get()
+    }
+
+    /// Stores the score for the key, always replacing the entry sharing its index
+    pub fn set(&mut self, key: u64, score: V) {
+        let index = key as usize % self.keys.len();
         self.keys[index] = key as u32;
         self.scores[index] = score;
     }
 
+    /// Writes the entries of the table, preceded by a header recording its size, so that it can be
+    /// read back with [TranspositionTable::load()]. The writer should be buffered, e.g. with a BufWriter.
+    #[cfg(feature = "std")]
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        // Tables have the smallest prime number of entries above 2^bits, which is below 2^(bits+1)
+        let bits = self.size().ilog2();
+        w.write_all(Self::FILE_MAGIC)?;
+        w.write_all(&bits.to_le_bytes())?;
+        for keys in self.keys.chunks(1 << 16) {
+            let bytes: Vec<u8> = keys.iter().flat_map(|key| key.to_le_bytes()).collect();
+            w.write_all(&bytes)?;
+        }
+        for scores in self.scores.chunks(1 << 16) {
+            let mut bytes = vec![0; V::BYTES * scores.len()];
+            for (score, bytes) in scores.iter().zip(bytes.chunks_exact_mut(V::BYTES)) {
+                score.write_le(bytes);
+            }
+            w.write_all(&bytes)?;
+        }
+        w.flush()
+    }
+
+    /// Reads a table written by [TranspositionTable::save()].
+    /// Returns an error of kind InvalidData if the header isn't valid, and UnexpectedEof if the entries are truncated.
+    #[cfg(feature = "std")]
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != Self::FILE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a transposition table file"));
+        }
+        let mut bits = [0; 4];
+        r.read_exact(&mut bits)?;
+        let bits = u32::from_le_bytes(bits);
+        let (min_bits, max_bits) = (TranspositionTable::MIN_BITS, TranspositionTable::MAX_BITS);
+        if !(min_bits..=max_bits).contains(&bits) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid table size 2^{bits}, expected 2^{min_bits} to 2^{max_bits}"),
+            ));
+        }
+
+        let mut table = Self::with_bits(bits);
+        let mut bytes = vec![0; 4 << 16];
+        for keys in table.keys.chunks_mut(1 << 16) {
+            let bytes = &mut bytes[..4 * keys.len()];
+            r.read_exact(bytes)?;
+            for (key, bytes) in keys.iter_mut().zip(bytes.chunks_exact(4)) {
+                *key = u32::from_le_bytes(bytes.try_into().unwrap());
+            }
+        }
+        let mut bytes = vec![0; V::BYTES << 16];
+        for scores in table.scores.chunks_mut(1 << 16) {
+            let bytes = &mut bytes[..V::BYTES * scores.len()];
+            r.read_exact(bytes)?;
+            for (score, bytes) in scores.iter_mut().zip(bytes.chunks_exact(V::BYTES)) {
+                *score = V::read_le(bytes);
+            }
+        }
+        Ok(table)
+    }
+
     pub fn clear(&mut self) {
         self.keys.fill(0);
-        self.scores.fill(0);
+        self.scores.fill(V::default());
+        self.stats.take();
+    }
+}
+
+// Returns the smallest prime number greater than or equal to n
+fn next_prime(n: usize) -> usize {
+    let is_prime = |n: usize| n >= 2 && (2..).take_while(|i| i * i <= n).all(|i| !n.is_multiple_of(i));
+    (n..).find(|&n| is_prime(n)).unwrap()
+}
+
+impl<V: TableValue> Default for GenericTranspositionTable<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [GenericTranspositionTable] that also stores the depth of the search that produced each entry, so that
+/// [DepthPreferredTable::set_with_depth()] doesn't evict expensive results with cheap ones.
+/// The depths take one more byte per entry.
+pub struct DepthPreferredTable<V: TableValue = u8> {
+    table: GenericTranspositionTable<V>,
+    depths: Box<[u8]>,
+}
+
+impl<V: TableValue> DepthPreferredTable<V> {
+    pub fn new() -> Self {
+        Self::with_bits(TranspositionTable::DEFAULT_BITS)
+    }
+
+    /// Creates a table with the smallest prime number of entries not below 2^bits, using 5 bytes per entry
+    /// plus the size of the value, see [TranspositionTable::with_bits()]
+    pub fn with_bits(bits: u32) -> Self {
+        let table = GenericTranspositionTable::with_bits(bits);
+        let depths = vec![0; table.size()].into_boxed_slice();
+        Self { table, depths }
+    }
+
+    /// Returns the number of entries of the table
+    pub fn size(&self) -> usize {
+        self.table.size()
+    }
+
+    /// Returns the score stored for the key, see [TranspositionTable::get()]
+    pub fn get(&self, key: u64) -> Option<V> {
+        self.table.get(key)
+    }
+
+    /// Returns the lookup counts since the table was created or last cleared
+    pub fn stats(&self) -> TableStats {
+        self.table.stats()
+    }
+
+    /// Stores the score for the key, always replacing the entry sharing its index
+    pub fn set(&mut self, key: u64, score: V) {
+        self.table.set(key, score);
+        self.depths[key as usize % self.depths.len()] = 0;
+    }
+
+    /// Stores the score for the key, found by a search of the given depth, e.g. the number of remaining moves.
+    /// The entry sharing its index is only replaced if it comes from a search at most as deep,
+    /// so that expensive results aren't evicted by cheap ones.
+    pub fn set_with_depth(&mut self, key: u64, score: V, depth: u8) {
+        let index = key as usize % self.depths.len();
+        if depth >= self.depths[index] {
+            self.table.set(key, score);
+            self.depths[index] = depth;
+        }
+    }
+
+    /// Writes the entries of the table as [GenericTranspositionTable::save()] does, followed by their depths
+    #[cfg(feature = "std")]
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        self.table.save(&mut w)?;
+        w.write_all(&self.depths)?;
+        w.flush()
+    }
+
+    /// Reads a table written by [DepthPreferredTable::save()], see [GenericTranspositionTable::load()]
+    #[cfg(feature = "std")]
+    pub fn load<R: Read>(mut r: R) -> io::Result<Self> {
+        let table = GenericTranspositionTable::load(&mut r)?;
+        let mut depths = vec![0; table.size()].into_boxed_slice();
+        r.read_exact(&mut depths)?;
+        Ok(Self { table, depths })
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+        self.depths.fill(0);
+    }
+}
+
+impl<V: TableValue> Default for DepthPreferredTable<V> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Default for TranspositionTable {
+/// A transposition table that can be shared by several threads, e.g. in an `Arc`: entries are read and
+/// written through `&self`. It has the same indexing and size as [TranspositionTable], and the replacement policies
+/// of [DepthPreferredTable].
+///
+/// Each entry is stored as two atomics, its score and depth, and the lower 32 bits of its key XORed with them,
+/// so that no lock is needed. An entry read while another thread writes it may mix the two writes,
+/// which almost never validates against the key and is then read as a miss. Entries can also be lost when two threads
+/// write the same index at once. Neither is a data race, only lost work.
+pub struct ConcurrentTranspositionTable {
+    keys: Box<[AtomicU32]>, // lower 32 bits of the key XORed with the data
+    data: Box<[AtomicU16]>, // score in the lower byte, depth in the upper one
+}
+
+impl ConcurrentTranspositionTable {
+    pub fn new() -> Self {
+        Self::with_bits(TranspositionTable::DEFAULT_BITS)
+    }
+
+    /// Creates a table with the smallest prime number of entries not below 2^bits, using 6 bytes per entry,
+    /// see [TranspositionTable::with_bits()]
+    pub fn with_bits(bits: u32) -> Self {
+        assert!(
+            bits >= TranspositionTable::MIN_BITS,
+            "Table must have at least 2^{} entries",
+            TranspositionTable::MIN_BITS
+        );
+        let size = next_prime(1 << bits);
+        Self {
+            keys: (0..size).map(|_| AtomicU32::new(0)).collect(),
+            data: (0..size).map(|_| AtomicU16::new(0)).collect(),
+        }
+    }
+
+    /// Returns the number of entries of the table
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns the score stored for the key, see [TranspositionTable::get()]
+    pub fn get(&self, key: u64) -> Option<u8> {
+        let index = key as usize % self.keys.len();
+        let data = self.data[index].load(Ordering::Relaxed);
+        let entry = self.keys[index].load(Ordering::Relaxed);
+        let score = data as u8;
+        (entry ^ data as u32 == key as u32 && score != 0).then_some(score)
+    }
+
+    /// Stores the score for the key, always replacing the entry sharing its index
+    pub fn set(&self, key: u64, score: u8) {
+        self.store(key as usize % self.keys.len(), key, score as u16);
+    }
+
+    /// Stores the score for the key, found by a search of the given depth, unless the entry sharing its index
+    /// comes from a deeper search, see [DepthPreferredTable::set_with_depth()]
+    pub fn set_with_depth(&self, key: u64, score: u8, depth: u8) {
+        let index = key as usize % self.keys.len();
+        if depth >= (self.data[index].load(Ordering::Relaxed) >> 8) as u8 {
+            self.store(index, key, score as u16 | (depth as u16) << 8);
+        }
+    }
+
+    fn store(&self, index: usize, key: u64, data: u16) {
+        self.keys[index].store(key as u32 ^ data as u32, Ordering::Relaxed);
+        self.data[index].store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.iter_mut().for_each(|key| *key.get_mut() = 0);
+        self.data.iter_mut().for_each(|data| *data.get_mut() = 0);
+    }
+}
+
+impl Default for ConcurrentTranspositionTable {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_empty_entries() {
+        let mut table = TranspositionTable::new();
+        assert_eq!(table.get(0), None);
+        assert_eq!(table.get(1 << 40), None);
+
+        table.set(1 << 40, 5);
+        assert_eq!(table.get(1 << 40), Some(5));
+        assert_eq!(table.get(0), None);
+
+        table.clear();
+        assert_eq!(table.get(1 << 40), None);
+    }
+
+    #[test]
+    fn test_with_bits() {
+        assert_eq!(TranspositionTable::new().size(), (1 << 23) + 9);
+        assert_eq!(TranspositionTable::with_bits(17).size(), 131101);
+        assert_eq!(TranspositionTable::with_bits(18).size(), 262147);
+
+        // Keys with the same index don't collide when the rest of the key is checked
+        let mut table = TranspositionTable::with_bits(17);
+        table.set(3, 1);
+        table.set(3 + 131101, 2);
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.get(3 + 131101), Some(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_bits_too_small() {
+        TranspositionTable::with_bits(16);
+    }
+
+    #[test]
+    fn test_set_with_depth() {
+        let mut table = DepthPreferredTable::<u8>::with_bits(17);
+        let size = table.size() as u64;
+
+        table.set_with_depth(3, 1, 10);
+        // A shallower entry with the same index doesn't evict the deeper one
+        table.set_with_depth(3 + size, 2, 5);
+        assert_eq!(table.get(3), Some(1));
+        assert_eq!(table.get(3 + size), None);
+
+        // An entry at least as deep replaces it, as does an entry stored with set
+        table.set_with_depth(3 + size, 2, 10);
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.get(3 + size), Some(2));
+        table.set(3, 1);
+        assert_eq!(table.get(3), Some(1));
+
+        table.clear();
+        table.set_with_depth(3 + size, 2, 0);
+        assert_eq!(table.get(3 + size), Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_depth_preferred_save_load() {
+        let mut table = DepthPreferredTable::<u8>::with_bits(17);
+        table.set_with_depth(3, 1, 10);
+        table.set(4, 2);
+
+        let mut file = Vec::new();
+        table.save(&mut file).unwrap();
+        let mut loaded = DepthPreferredTable::<u8>::load(file.as_slice()).unwrap();
+        assert_eq!((loaded.get(3), loaded.get(4)), (Some(1), Some(2)));
+        assert_eq!(loaded.depths, table.depths);
+        // The depths are still used for replacing entries
+        loaded.set_with_depth(3 + table.size() as u64, 5, 5);
+        assert_eq!(loaded.get(3), Some(1));
+
+        // The table without the depths is a table of the same values
+        let plain = TranspositionTable::load(file.as_slice()).unwrap();
+        assert_eq!((plain.get(3), plain.get(4)), (Some(1), Some(2)));
+        let mut file = Vec::new();
+        plain.save(&mut file).unwrap();
+        let error = DepthPreferredTable::<u8>::load(file.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut table = TranspositionTable::with_bits(17);
+        let size = table.size() as u64;
+        assert_eq!(table.stats(), TableStats::default());
+        assert_eq!(table.stats().hit_rate(), 0.0);
+
+        table.set(3, 1);
+        table.get(3);
+        table.get(3);
+        table.get(4);
+        table.get(3 + size);
+        assert_eq!(table.stats(), TableStats { hits: 2, misses: 2, collisions: 1 });
+        assert_eq!(table.stats().hit_rate(), 0.5);
+
+        table.clear();
+        assert_eq!(table.stats(), TableStats::default());
+    }
+
+    #[test]
+    fn test_concurrent_table() {
+        let mut table = ConcurrentTranspositionTable::with_bits(17);
+        let size = table.size() as u64;
+        assert_eq!(size, 131101);
+        assert_eq!(table.get(0), None);
+
+        table.set(1 << 40, 5);
+        assert_eq!(table.get(1 << 40), Some(5));
+        assert_eq!(table.get(0), None);
+        table.set_with_depth(3, 1, 10);
+        table.set_with_depth(3 + size, 2, 5);
+        assert_eq!(table.get(3), Some(1));
+        assert_eq!(table.get(3 + size), None);
+        table.set_with_depth(3 + size, 2, 10);
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.get(3 + size), Some(2));
+
+        table.clear();
+        assert_eq!(table.get(1 << 40), None);
+        assert_eq!(table.get(3 + size), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_concurrent_table_stress() {
+        // Few entries for many keys, so that threads keep overwriting each other's entries
+        let table = ConcurrentTranspositionTable::with_bits(17);
+        let size = table.size() as u64;
+        let score = |key: u64| (key % 255) as u8 + 1;
+        let rounds = if cfg!(miri) { 50 } else { 20_000 };
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..rounds {
+                        // Keys sharing the first few indices, with different lower 32 bits
+                        let key = (i * 7 + thread) % 16 + (i % 13) * size;
+                        if i % 3 == 0 {
+                            table.set_with_depth(key, score(key), (i % 5) as u8);
+                        } else if let Some(found) = table.get(key) {
+                            // A torn entry is never read as a hit for the wrong score
+                            assert_eq!(found, score(key));
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_save_load() {
+        let mut table = TranspositionTable::with_bits(17);
+        let keys = [3, 1 << 40, 123_456_789_012, 131101 + 7];
+        for (i, &key) in keys.iter().enumerate() {
+            table.set(key, i as u8 + 1);
+        }
+
+        let mut file = Vec::new();
+        table.save(&mut file).unwrap();
+        let loaded = TranspositionTable::load(file.as_slice()).unwrap();
+        assert_eq!(loaded.size(), table.size());
+        for key in keys.into_iter().chain([0, 4, 1 << 41]) {
+            assert_eq!(loaded.get(key), table.get(key));
+        }
+
+        // Truncated entries and invalid headers are errors
+        let error = TranspositionTable::load(&file[..file.len() - 1]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        let error = TranspositionTable::load(&file[1..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        file[4] = 16;
+        let error = TranspositionTable::load(file.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_wide_values() {
+        let mut table = GenericTranspositionTable::<u16>::with_bits(17);
+        let size = table.size() as u64;
+        table.set(3, 0x1234);
+        table.set(4, 0x100);
+        assert_eq!(table.get(3), Some(0x1234));
+        assert_eq!(table.get(4), Some(0x100));
+        assert_eq!(table.get(4 + size), None);
+
+        #[cfg(feature = "std")]
+        {
+            let mut file = Vec::new();
+            table.save(&mut file).unwrap();
+            assert_eq!(&file[..4], b"C4T2");
+            let loaded = GenericTranspositionTable::<u16>::load(file.as_slice()).unwrap();
+            for key in [3, 4, 5, 4 + size] {
+                assert_eq!(loaded.get(key), table.get(key));
+            }
+
+            // A table can't be loaded as one of another value type
+            let error = TranspositionTable::load(file.as_slice()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+
+        table.clear();
+        assert_eq!(table.get(3), None);
+    }
+}
@@ -0,0 +1,67 @@
+//! JavaScript bindings of the solver, built with the wasm feature.
+//!
+//! Positions are passed as notation strings, see [BitBoard::try_from_notation()], and columns are returned
+//! as indices from 0 to 6. Invalid arguments are thrown as JavaScript errors instead of panicking.
+
+use crate::board::BitBoard;
+use crate::solver::Solver;
+use crate::transposition_table::TranspositionTable;
+use alloc::format;
+use alloc::string::ToString;
+use wasm_bindgen::prelude::*;
+
+// Largest table accepted: 5 * 2^28 bytes, plus the two tables of an eighth of the entries, is 1.7GB of the 4GB
+// a 32-bit WASM memory can hold. 2^size_bits must also fit the 32 bits of a usize
+const MAX_SIZE_BITS: u32 = 28;
+
+#[wasm_bindgen]
+pub struct WasmSolver {
+    solver: Solver,
+}
+
+#[wasm_bindgen]
+impl WasmSolver {
+    /// Creates a solver whose transposition table has about 2^size_bits entries, of 5 bytes each.
+    /// size_bits must be from 17 to 28.
+    #[wasm_bindgen(constructor)]
+    pub fn new(size_bits: u32) -> Result<WasmSolver, JsError> {
+        if !(TranspositionTable::MIN_BITS..=MAX_SIZE_BITS).contains(&size_bits) {
+            return Err(JsError::new(&format!(
+                "table must have from 2^{} to 2^{MAX_SIZE_BITS} entries",
+                TranspositionTable::MIN_BITS
+            )));
+        }
+        Ok(WasmSolver { solver: Solver::with_table_bits(size_bits) })
+    }
+
+    /// Returns the score of the position for the current player, see [Solver::solve()]
+    pub fn solve(&mut self, notation: &str) -> Result<i32, JsError> {
+        let position = parse(notation)?;
+        Ok(self.solver.solve(&position).score)
+    }
+
+    /// Returns the index of the best column for the current player, or undefined if the board is full
+    pub fn best_move(&mut self, notation: &str) -> Result<Option<u8>, JsError> {
+        let position = parse(notation)?;
+        Ok(self.solver.best_move(&position).map(|(column, _)| column as u8))
+    }
+}
+
+fn parse(notation: &str) -> Result<BitBoard, JsError> {
+    BitBoard::try_from_notation(notation).map_err(|error| JsError::new(&error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only the successful calls can be tested outside of WASM, creating a JsError calls into JavaScript
+    #[test]
+    fn test_wasm_solver() {
+        let mut solver = WasmSolver::new(17).ok().unwrap();
+        assert_eq!(solver.solve("7422341735647741166133573473242566").ok(), Some(1));
+        // Both C and G win, C is closer to the center
+        assert_eq!(solver.best_move("445566").ok(), Some(Some(2)));
+        assert_eq!(solver.best_move("675424577317223371452513267643443266156151").ok(), Some(None));
+    }
+}